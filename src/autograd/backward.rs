@@ -0,0 +1,553 @@
+use ndarray::{Array, ArrayD, Axis, Dimension, IxDyn, Slice};
+use std::collections::{HashMap, HashSet};
+use std::ops::AddAssign;
+
+use crate::autograd::{
+    Autograd, Element, Function, Op, batched_dot, extremum_mask, gelu_inner, inverse_permutation,
+    keepdims_shape, reshape, select_weights, sigmoid,
+};
+use crate::error::{Error, Result};
+
+/// How `backward_with_options` runs a backward pass.
+#[derive(Debug, Clone)]
+pub struct BackwardOptions<T: Element = f64> {
+    /// Upstream gradient of the root. `None` seeds a single-element root with 1.
+    pub grad: Option<ArrayD<T>>,
+    /// Keep the graph after the pass so that it can be backpropagated again.
+    /// Otherwise the edges of every interior node are freed once its gradient
+    /// has been propagated.
+    pub retain_graph: bool,
+}
+
+impl<T: Element> BackwardOptions<T> {
+    pub fn new() -> Self {
+        BackwardOptions {
+            grad: None,
+            retain_graph: false,
+        }
+    }
+
+    pub fn grad<D: Dimension>(mut self, grad: &Array<T, D>) -> Self {
+        self.grad = Some(grad.clone().into_dyn());
+        self
+    }
+
+    pub fn retain_graph(mut self, retain_graph: bool) -> Self {
+        self.retain_graph = retain_graph;
+        self
+    }
+}
+
+impl<T: Element> Default for BackwardOptions<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Element> Autograd<T> {
+    /// Backpropagate from a single-element root such as a loss, seeding its
+    /// gradient with 1. Other roots need `backward_with`.
+    ///
+    /// The graph is released afterwards; use `backward_with_options` with
+    /// `retain_graph` to backpropagate through it more than once.
+    pub fn backward(&self) -> Result<()> {
+        self.backward_with_options(BackwardOptions::new())
+    }
+
+    /// Backpropagate `grad`, the gradient of some downstream quantity with
+    /// respect to this node. It must have the same shape as the node's value.
+    pub fn backward_with<D: Dimension>(&self, grad: &Array<T, D>) -> Result<()> {
+        self.backward_with_options(BackwardOptions::new().grad(grad))
+    }
+
+    pub fn backward_with_options(&self, options: BackwardOptions<T>) -> Result<()> {
+        let topo = self.get_topo();
+        check_graph(self, &topo)?;
+        let seed = match &options.grad {
+            Some(grad) => self.seed(grad)?,
+            None => self.seed_ones()?,
+        };
+        let root = self.as_ptr();
+        self.propagate(topo, seed, options.retain_graph, |node, grad| {
+            let mut data = node.try_data_mut()?;
+            if node.as_ptr() == root {
+                // The root's grad is the seed, as rewritten by its hooks
+                data.grad = Some(grad.clone());
+            } else {
+                data.accumulate_grad(grad);
+            }
+            Ok(())
+        })
+    }
+
+    // Gradients of this node with respect to `inputs` given the gradient `seed`
+    // of the node itself. Unlike `backward` this writes no node's `grad` and
+    // keeps the graph, so it can be called on graphs the caller still uses.
+    //
+    // The inputs are leaves created after any earlier pass, so no released
+    // node can lead to them and such nodes are simply treated as leaves.
+    pub(crate) fn gradients(
+        &self,
+        seed: ArrayD<T>,
+        inputs: &[Autograd<T>],
+    ) -> Result<Vec<ArrayD<T>>> {
+        check_root(self)?;
+        let topo = self.get_topo();
+        let wanted: HashSet<*const ()> = inputs.iter().map(|x| x.as_ptr()).collect();
+        let mut found: HashMap<*const (), ArrayD<T>> = HashMap::new();
+        self.propagate(topo, seed, true, |node, grad| {
+            if wanted.contains(&node.as_ptr()) {
+                found.insert(node.as_ptr(), grad.clone());
+            }
+            Ok(())
+        })?;
+
+        inputs
+            .iter()
+            .map(|x| match found.get(&x.as_ptr()) {
+                Some(grad) => Ok(grad.clone()),
+                None => Ok(ArrayD::zeros(x.try_data()?.value().raw_dim())),
+            })
+            .collect()
+    }
+
+    // A gradient of ones for a single-element node
+    pub(crate) fn seed_ones(&self) -> Result<ArrayD<T>> {
+        let shape = self.try_data()?.value().raw_dim();
+        if shape.size() != 1 {
+            return Err(Error::NonScalarRoot {
+                shape: shape.slice().to_vec(),
+            });
+        }
+        Ok(ArrayD::ones(shape))
+    }
+
+    pub(crate) fn seed<D: Dimension>(&self, grad: &Array<T, D>) -> Result<ArrayD<T>> {
+        let expected = self.try_data()?.value().shape().to_vec();
+        if grad.shape() != expected.as_slice() {
+            return Err(Error::GradShape {
+                expected,
+                got: grad.shape().to_vec(),
+            });
+        }
+        Ok(grad.clone().into_dyn())
+    }
+
+    // Walk the graph in reverse topological order, handing each node's complete
+    // gradient to `finalize` before propagating it to the node's children. The
+    // order is consumed as it goes, so that a released node is freed as soon as
+    // nothing else refers to it.
+    fn propagate<F>(
+        &self,
+        mut topo: Vec<Autograd<T>>,
+        seed: ArrayD<T>,
+        retain_graph: bool,
+        mut finalize: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&Autograd<T>, &ArrayD<T>) -> Result<()>,
+    {
+        // Gradients of this pass are collected here rather than in the nodes, so
+        // that what earlier passes left in `grad` is never propagated again
+        let mut grads: HashMap<*const (), ArrayD<T>> = HashMap::new();
+        grads.insert(self.as_ptr(), seed);
+
+        while let Some(node) = topo.pop() {
+            // All parents come before a node in reverse topological order, so its
+            // gradient is complete by the time it is reached
+            let Some(grad) = grads.remove(&node.as_ptr()) else {
+                continue;
+            };
+            let grad = node.run_hooks(grad)?;
+            finalize(&node, &grad)?;
+
+            let data = node.try_data()?;
+            if let Op::None | Op::Released = data.op {
+                continue;
+            }
+            let child_grads = {
+                let child_data = data
+                    .children
+                    .iter()
+                    .map(|c| c.try_data())
+                    .collect::<Result<Vec<_>>>()?;
+                let values: Vec<_> = child_data.iter().map(|d| d.value()).collect();
+                let inputs: Vec<_> = values.iter().map(|v| &**v).collect();
+                let needs: Vec<_> = child_data.iter().map(|d| d.requires_grad).collect();
+                vjp(&data.op, &inputs, &data.value(), &grad, &needs)?
+            };
+
+            for (child, g) in data.children.iter().zip(child_grads) {
+                let Some(g) = g else {
+                    continue;
+                };
+                match grads.get_mut(&child.as_ptr()) {
+                    Some(acc) => *acc += &g,
+                    None => {
+                        grads.insert(child.as_ptr(), g);
+                    }
+                }
+            }
+            drop(data);
+
+            if !retain_graph {
+                let mut data = node.try_data_mut()?;
+                data.children.clear();
+                data.op = Op::Released;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Refuse to backpropagate from a root that needs no gradient, or through a graph
+// whose edges an earlier pass freed. Both would silently leave the inputs
+// without gradients.
+pub(crate) fn check_graph<T: Element>(root: &Autograd<T>, topo: &[Autograd<T>]) -> Result<()> {
+    check_root(root)?;
+    for node in topo {
+        if let Op::Released = node.try_data()?.op {
+            return Err(Error::GraphReleased {
+                name: node.try_name(),
+            });
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn check_root<T: Element>(root: &Autograd<T>) -> Result<()> {
+    if !root.try_data()?.requires_grad {
+        return Err(Error::NoGrad {
+            name: root.try_name(),
+        });
+    }
+    Ok(())
+}
+
+// Gradient of each input of `op` given the gradient of its output, computed only
+// for the inputs flagged in `needs`. `None` marks inputs that get no gradient.
+pub(crate) fn vjp<T: Element>(
+    op: &Op<T>,
+    inputs: &[&ArrayD<T>],
+    output: &ArrayD<T>,
+    grad: &ArrayD<T>,
+    needs: &[bool],
+) -> Result<Vec<Option<ArrayD<T>>>> {
+    // This also covers every op with a single input
+    if !needs.contains(&true) {
+        return Ok(vec![None; inputs.len()]);
+    }
+    let both = |da: &dyn Fn() -> ArrayD<T>, db: &dyn Fn() -> ArrayD<T>| {
+        vec![needs[0].then(da), needs[1].then(db)]
+    };
+    Ok(match op {
+        Op::Add => {
+            // y = a + b -> da = dy, db = dy
+            inputs
+                .iter()
+                .zip(needs)
+                .map(|(x, &need)| need.then(|| unbroadcast(grad, &x.raw_dim())))
+                .collect()
+        }
+        Op::Sub => {
+            // y = a - b -> da = dy, db = -dy
+            both(&|| unbroadcast(grad, &inputs[0].raw_dim()), &|| {
+                unbroadcast(&-grad.clone(), &inputs[1].raw_dim())
+            })
+        }
+        Op::Mul => {
+            // y = a * b (elementwise) -> da = dy * b, db = dy * a
+            let (a, b) = (inputs[0], inputs[1]);
+            both(&|| unbroadcast(&(grad * b), &a.raw_dim()), &|| {
+                unbroadcast(&(grad * a), &b.raw_dim())
+            })
+        }
+        Op::MatMul => {
+            // y = a @ b -> da = dy @ b^T, db = a^T @ dy
+            let (a, b) = (inputs[0], inputs[1]);
+            both(&|| batched_dot(grad, &transpose_last(b)), &|| {
+                if b.ndim() == 2 {
+                    // b was shared across the batch, so fold the batch into the rows
+                    let k = a.shape()[a.ndim() - 1];
+                    let m = grad.shape()[grad.ndim() - 1];
+                    let a2 = a.to_shape((a.len() / k, k)).unwrap();
+                    let g2 = grad.to_shape((grad.len() / m, m)).unwrap();
+                    a2.t().dot(&g2).into_dyn()
+                } else {
+                    batched_dot(&transpose_last(a), grad)
+                }
+            })
+        }
+        Op::Div => {
+            // y = a / b -> dy/da = 1/b, dy/db = -a/b^2
+            let (a, b) = (inputs[0], inputs[1]);
+            both(&|| unbroadcast(&(grad / b), &a.raw_dim()), &|| {
+                let db = -(a / &b.mapv(|x| x * x)) * grad;
+                unbroadcast(&db, &b.raw_dim())
+            })
+        }
+        Op::Pow => {
+            // y = x^p -> dy/dx = p * x^(p-1)
+            let power = inputs[1].sum();
+            let local_deriv = inputs[0].mapv(|x| power * x.powf(power - T::one()));
+            vec![Some(grad * &local_deriv), None]
+        }
+        Op::Log => {
+            // y = log(x) -> dy/dx = 1/x
+            vec![Some(grad / inputs[0])]
+        }
+        Op::Neg => {
+            // y = -x -> dy/dx = -1
+            vec![Some(-grad.clone())]
+        }
+        Op::Exp => {
+            // y = exp(x) -> dy/dx = exp(x)
+            vec![Some(grad * output)]
+        }
+        Op::Tanh => {
+            // y = tanh(x) -> dy/dx = 1 - tanh(x)^2
+            let local_deriv = output.mapv(|x| T::one() - x * x);
+            vec![Some(local_deriv * grad)]
+        }
+        Op::Sigmoid => {
+            // y = sigmoid(x) -> dy/dx = y * (1 - y)
+            let local_deriv = output.mapv(|y| y * (T::one() - y));
+            vec![Some(local_deriv * grad)]
+        }
+        Op::Gelu => {
+            // y = 0.5 * x * (1 + tanh(u)), u = c * (x + k * x^3)
+            // -> dy/dx = 0.5 * (1 + tanh(u)) + 0.5 * x * (1 - tanh(u)^2) * c * (1 + 3k * x^2)
+            let half = T::from_f64(0.5).unwrap();
+            let c = T::from_f64((2.0 / std::f64::consts::PI).sqrt()).unwrap();
+            let k3 = T::from_f64(3.0 * 0.044715).unwrap();
+            let local_deriv = inputs[0].mapv(|x| {
+                let t = gelu_inner(x).tanh();
+                half * (T::one() + t) + half * x * (T::one() - t * t) * c * (T::one() + k3 * x * x)
+            });
+            vec![Some(local_deriv * grad)]
+        }
+        Op::SiLU => {
+            // y = x * sigmoid(x) -> dy/dx = s * (1 + x * (1 - s))
+            let local_deriv = inputs[0].mapv(|x| {
+                let s = sigmoid(x);
+                s * (T::one() + x * (T::one() - s))
+            });
+            vec![Some(local_deriv * grad)]
+        }
+        Op::LeakyReLU(slope) => {
+            // y = leaky_relu(x) -> dy/dx = 1 if x > 0, slope otherwise
+            let local_deriv = inputs[0].mapv(|x| if x > T::zero() { T::one() } else { *slope });
+            vec![Some(local_deriv * grad)]
+        }
+        Op::Elu(alpha) => {
+            // y = alpha * (exp(x) - 1) for x <= 0 -> dy/dx = y + alpha there, 1 otherwise
+            let local_deriv = ndarray::Zip::from(inputs[0])
+                .and(output)
+                .map_collect(|&x, &y| if x > T::zero() { T::one() } else { y + *alpha });
+            vec![Some(local_deriv * grad)]
+        }
+        Op::Softplus => {
+            // y = log(1 + exp(x)) -> dy/dx = sigmoid(x)
+            vec![Some(inputs[0].mapv(sigmoid) * grad)]
+        }
+        Op::Sqrt => {
+            // y = sqrt(x) -> dy/dx = 1 / (2 * y)
+            vec![Some(grad / &output.mapv(|y| y + y))]
+        }
+        Op::Abs => {
+            // y = |x| -> dy/dx = sign(x), taking 0 at 0
+            let sign = inputs[0].mapv(|x| {
+                if x > T::zero() {
+                    T::one()
+                } else if x < T::zero() {
+                    -T::one()
+                } else {
+                    T::zero()
+                }
+            });
+            vec![Some(sign * grad)]
+        }
+        Op::Sin => {
+            // y = sin(x) -> dy/dx = cos(x)
+            vec![Some(inputs[0].mapv(|x| x.cos()) * grad)]
+        }
+        Op::Cos => {
+            // y = cos(x) -> dy/dx = -sin(x)
+            vec![Some(inputs[0].mapv(|x| -x.sin()) * grad)]
+        }
+        Op::Clamp { min, max } => {
+            // y = clamp(x) -> dy/dx = 1 within [min, max], 0 outside
+            let mask = inputs[0].mapv(|x| {
+                if x >= *min && x <= *max {
+                    T::one()
+                } else {
+                    T::zero()
+                }
+            });
+            vec![Some(mask * grad)]
+        }
+        Op::Minimum | Op::Maximum => {
+            // y = min(a, b) -> da = dy where a < b, db = dy where b < a
+            let (a, b) = (inputs[0], inputs[1]);
+            let weight = select_weights(a, b, matches!(op, Op::Minimum));
+            both(&|| unbroadcast(&(grad * &weight), &a.raw_dim()), &|| {
+                let db = grad * &weight.mapv(|w| T::one() - w);
+                unbroadcast(&db, &b.raw_dim())
+            })
+        }
+        Op::Where(cond) => {
+            // y = cond ? a : b -> da = dy where cond, db = dy elsewhere
+            let (a, b) = (inputs[0], inputs[1]);
+            let mask = cond
+                .broadcast(grad.raw_dim())
+                .unwrap()
+                .mapv(|c| if c { T::one() } else { T::zero() });
+            both(&|| unbroadcast(&(grad * &mask), &a.raw_dim()), &|| {
+                let db = grad * &mask.mapv(|m| T::one() - m);
+                unbroadcast(&db, &b.raw_dim())
+            })
+        }
+        Op::StopGradient => {
+            // y = x, but no gradient flows back
+            vec![None]
+        }
+        Op::ReLU => {
+            // y = relu(x) -> dy/dx = 1 if x > 0, 0 otherwise
+            let mask = output.mapv(|x| if x > T::zero() { T::one() } else { T::zero() });
+            vec![Some(mask * grad)]
+        }
+        Op::Sum { axis, .. } | Op::Mean { axis, .. } => {
+            // y = sum(x) -> dy/dx = 1, spread back over the reduced axis
+            let input_shape = inputs[0].raw_dim();
+            let count = match axis {
+                Some(axis) => input_shape[*axis],
+                None => input_shape.size(),
+            };
+            let mut local = reshape(grad, keepdims_shape(input_shape.slice(), *axis))
+                .broadcast(input_shape)
+                .unwrap()
+                .to_owned();
+            if let Op::Mean { .. } = op {
+                local.mapv_inplace(|g| g / T::from_usize(count).unwrap());
+            }
+            vec![Some(local)]
+        }
+        Op::Max { axis, .. } | Op::Min { axis, .. } => {
+            // y = max(x) -> dy/dx = 1 at the (first) argmax, 0 elsewhere
+            let mask = extremum_mask(inputs[0], output, *axis);
+            let grad = reshape(grad, keepdims_shape(inputs[0].shape(), *axis));
+            vec![Some(mask * &grad)]
+        }
+        Op::LogSumExp { axis, .. } => {
+            // y = log(sum(exp(x))) -> dy/dx = exp(x - y) = softmax(x)
+            let keepdims = keepdims_shape(inputs[0].shape(), *axis);
+            let softmax = (inputs[0] - &reshape(output, keepdims.clone())).mapv(|x| x.exp());
+            vec![Some(softmax * &reshape(grad, keepdims))]
+        }
+        Op::Softmax(axis) => {
+            // y = softmax(x) -> dx = y * (dy - sum(dy * y))
+            let dot = (grad * output)
+                .sum_axis(Axis(*axis))
+                .insert_axis(Axis(*axis));
+            vec![Some(output * &(grad - &dot))]
+        }
+        Op::LogSoftmax(axis) => {
+            // y = log_softmax(x) -> dx = dy - softmax(x) * sum(dy)
+            let total = grad.sum_axis(Axis(*axis)).insert_axis(Axis(*axis));
+            vec![Some(grad - &(output.mapv(|y| y.exp()) * &total))]
+        }
+        Op::Reshape(_) => {
+            // y = reshape(x) -> dx = reshape(dy) back to the input shape
+            vec![Some(reshape(grad, inputs[0].raw_dim()))]
+        }
+        Op::Permute(axes) => {
+            // y = permute(x, p) -> dx = permute(dy, p^-1)
+            let inverse = inverse_permutation(axes);
+            vec![Some(grad.clone().permuted_axes(inverse))]
+        }
+        Op::Concat(axis) => {
+            // y = concat(x_1..x_n) -> dx_i = the slice of dy x_i was copied into
+            let mut start = 0;
+            inputs
+                .iter()
+                .zip(needs)
+                .map(|(x, &need)| {
+                    let len = x.shape()[*axis];
+                    start += len;
+                    let part = grad.slice_axis(Axis(*axis), Slice::from(start - len..start));
+                    need.then(|| part.to_owned())
+                })
+                .collect()
+        }
+        Op::Stack(axis) => {
+            // y = stack(x_1..x_n) -> dx_i = dy[.., i, ..]
+            (0..inputs.len())
+                .map(|i| needs[i].then(|| grad.index_axis(Axis(*axis), i).to_owned()))
+                .collect()
+        }
+        Op::Slice { axis, start, end } => {
+            // y = x[start..end] -> dx = dy scattered into zeros
+            let mut local = ArrayD::zeros(inputs[0].raw_dim());
+            local
+                .slice_axis_mut(Axis(*axis), Slice::from(*start..*end))
+                .add_assign(grad);
+            vec![Some(local)]
+        }
+        Op::Custom(function) => custom_backward(function.as_ref(), inputs, output, grad)?
+            .into_iter()
+            .zip(needs)
+            .map(|(g, &need)| need.then_some(g))
+            .collect(),
+        Op::Released | Op::None => Vec::new(),
+    })
+}
+
+// Gradients of a custom function, checked to be one per input and of its shape
+pub(super) fn custom_backward<T: Element>(
+    function: &dyn Function<T>,
+    inputs: &[&ArrayD<T>],
+    output: &ArrayD<T>,
+    grad: &ArrayD<T>,
+) -> Result<Vec<ArrayD<T>>> {
+    let grads = function.backward(inputs, output, grad);
+    if grads.len() != inputs.len() {
+        return Err(Error::ShapeMismatch {
+            op: "Function::backward",
+            shapes: vec![vec![inputs.len()], vec![grads.len()]],
+            names: vec!["inputs".to_string(), "gradients".to_string()],
+        });
+    }
+    if let Some((x, g)) = inputs
+        .iter()
+        .zip(&grads)
+        .find(|(x, g)| x.shape() != g.shape())
+    {
+        return Err(Error::GradShape {
+            expected: x.shape().to_vec(),
+            got: g.shape().to_vec(),
+        });
+    }
+    Ok(grads)
+}
+
+// Sum-reduce a broadcast gradient back to the shape of the operand it flows into
+fn unbroadcast<T: Element>(grad: &ArrayD<T>, shape: &IxDyn) -> ArrayD<T> {
+    let mut reduced = grad.clone();
+    // Broadcasting prepends axes, so drop the extra leading ones first
+    while reduced.ndim() > shape.ndim() {
+        reduced = reduced.sum_axis(Axis(0));
+    }
+    for (axis, &dim) in shape.slice().iter().enumerate() {
+        if dim == 1 && reduced.shape()[axis] != 1 {
+            reduced = reduced.sum_axis(Axis(axis)).insert_axis(Axis(axis));
+        }
+    }
+    reduced
+}
+
+// Swap the last two axes, the batched counterpart of `.t()`
+fn transpose_last<T: Element>(a: &ArrayD<T>) -> ArrayD<T> {
+    let mut view = a.view();
+    view.swap_axes(a.ndim() - 2, a.ndim() - 1);
+    view.to_owned()
+}
@@ -0,0 +1,265 @@
+use ndarray::{Array, Dimension};
+use std::cell::{Ref, RefMut};
+
+use crate::autograd::{Autograd, AutogradData, Element};
+use crate::error::{Error, Result};
+
+// Shape-checked versions of the ops that can be fed incompatible operands or
+// axes. They return an error instead of letting ndarray panic, and otherwise
+// behave exactly like the unchecked op. Ops on a single tensor report its shape
+// followed by the shape or axes they were asked for.
+impl<T: Element> Autograd<T> {
+    pub fn try_add(&self, other: &Autograd<T>) -> Result<Autograd<T>> {
+        self.check_binary(other, "add", broadcast_shape)?;
+        Ok(Autograd::add(self, other))
+    }
+
+    pub fn try_sub(&self, other: &Autograd<T>) -> Result<Autograd<T>> {
+        self.check_binary(other, "sub", broadcast_shape)?;
+        Ok(Autograd::sub(self, other))
+    }
+
+    pub fn try_mul(&self, other: &Autograd<T>) -> Result<Autograd<T>> {
+        self.check_binary(other, "mul", broadcast_shape)?;
+        Ok(Autograd::mul(self, other))
+    }
+
+    pub fn try_div(&self, other: &Autograd<T>) -> Result<Autograd<T>> {
+        self.check_binary(other, "div", broadcast_shape)?;
+        Ok(Autograd::div(self, other))
+    }
+
+    pub fn try_minimum(&self, other: &Autograd<T>) -> Result<Autograd<T>> {
+        self.check_binary(other, "minimum", broadcast_shape)?;
+        Ok(self.minimum(other))
+    }
+
+    pub fn try_maximum(&self, other: &Autograd<T>) -> Result<Autograd<T>> {
+        self.check_binary(other, "maximum", broadcast_shape)?;
+        Ok(self.maximum(other))
+    }
+
+    pub fn try_matmul(&self, other: &Autograd<T>) -> Result<Autograd<T>> {
+        self.check_binary(other, "matmul", matmul_shape)?;
+        Ok(self.matmul(other))
+    }
+
+    pub fn try_reshape(&self, shape: &[usize]) -> Result<Autograd<T>> {
+        let data = self.try_data()?;
+        if data.value().len() != shape.iter().product::<usize>() {
+            return Err(Error::ShapeMismatch {
+                op: "reshape",
+                shapes: vec![data.value().shape().to_vec(), shape.to_vec()],
+                names: vec![data.name.clone()],
+            });
+        }
+        drop(data);
+        Ok(self.reshape(shape))
+    }
+
+    pub fn try_permute(&self, axes: &[usize]) -> Result<Autograd<T>> {
+        self.check_args("permute", axes, |shape| {
+            let mut sorted = axes.to_vec();
+            sorted.sort_unstable();
+            sorted.into_iter().eq(0..shape.len())
+        })?;
+        Ok(self.permute(axes))
+    }
+
+    pub fn try_slice_axis(&self, axis: usize, start: usize, end: usize) -> Result<Autograd<T>> {
+        self.check_args("slice_axis", &[axis, start, end], |shape| {
+            axis < shape.len() && start <= end && end <= shape[axis]
+        })?;
+        Ok(self.slice_axis(axis, start, end))
+    }
+
+    pub fn try_sum_axis(&self, axis: usize, keepdims: bool) -> Result<Autograd<T>> {
+        self.check_axis("sum_axis", axis)?;
+        Ok(self.sum_axis(axis, keepdims))
+    }
+
+    pub fn try_mean_axis(&self, axis: usize, keepdims: bool) -> Result<Autograd<T>> {
+        self.check_axis("mean_axis", axis)?;
+        Ok(self.mean_axis(axis, keepdims))
+    }
+
+    pub fn try_max_axis(&self, axis: usize, keepdims: bool) -> Result<Autograd<T>> {
+        self.check_axis("max_axis", axis)?;
+        Ok(self.max_axis(axis, keepdims))
+    }
+
+    pub fn try_min_axis(&self, axis: usize, keepdims: bool) -> Result<Autograd<T>> {
+        self.check_axis("min_axis", axis)?;
+        Ok(self.min_axis(axis, keepdims))
+    }
+
+    pub fn try_logsumexp_axis(&self, axis: usize, keepdims: bool) -> Result<Autograd<T>> {
+        self.check_axis("logsumexp_axis", axis)?;
+        Ok(self.logsumexp_axis(axis, keepdims))
+    }
+
+    pub fn try_concat(tensors: &[Autograd<T>], axis: usize) -> Result<Autograd<T>> {
+        check_all(tensors, "concat", |shapes| {
+            let first = &shapes[0];
+            axis < first.len()
+                && shapes.iter().all(|shape| {
+                    shape.len() == first.len()
+                        && (0..shape.len()).all(|i| i == axis || shape[i] == first[i])
+                })
+        })?;
+        Ok(Autograd::concat(tensors, axis))
+    }
+
+    pub fn try_stack(tensors: &[Autograd<T>], axis: usize) -> Result<Autograd<T>> {
+        check_all(tensors, "stack", |shapes| {
+            axis <= shapes[0].len() && shapes.iter().all(|shape| *shape == shapes[0])
+        })?;
+        Ok(Autograd::stack(tensors, axis))
+    }
+
+    pub fn try_where_<D: Dimension>(
+        cond: &Array<bool, D>,
+        a: &Autograd<T>,
+        b: &Autograd<T>,
+    ) -> Result<Autograd<T>> {
+        let (lhs, rhs) = (a.try_data()?, b.try_data()?);
+        let shape = broadcast_shape(cond.shape(), lhs.value().shape())
+            .and_then(|shape| broadcast_shape(&shape, rhs.value().shape()));
+        if shape.is_none() {
+            return Err(Error::ShapeMismatch {
+                op: "where_",
+                shapes: vec![
+                    cond.shape().to_vec(),
+                    lhs.value().shape().to_vec(),
+                    rhs.value().shape().to_vec(),
+                ],
+                names: vec![String::new(), lhs.name.clone(), rhs.name.clone()],
+            });
+        }
+        drop((lhs, rhs));
+        Ok(Autograd::where_(cond, a, b))
+    }
+
+    // Borrow the node's data, reporting a node that is already mutably borrowed
+    // as an error rather than panicking
+    pub(super) fn try_data(&self) -> Result<Ref<'_, AutogradData<T>>> {
+        self.data.try_borrow().map_err(|_| Error::Borrowed {
+            name: self.try_name(),
+        })
+    }
+
+    pub(super) fn try_data_mut(&self) -> Result<RefMut<'_, AutogradData<T>>> {
+        self.data.try_borrow_mut().map_err(|_| Error::Borrowed {
+            name: self.try_name(),
+        })
+    }
+
+    // Name of the node for error messages, if it can be read at all
+    pub(super) fn try_name(&self) -> String {
+        match self.data.try_borrow() {
+            Ok(data) => data.name.clone(),
+            Err(_) => String::new(),
+        }
+    }
+
+    fn check_axis(&self, op: &'static str, axis: usize) -> Result<()> {
+        self.check_args(op, &[axis], |shape| axis < shape.len())
+    }
+
+    // Check that `args` fit the shape of this node
+    fn check_args(
+        &self,
+        op: &'static str,
+        args: &[usize],
+        fits: impl FnOnce(&[usize]) -> bool,
+    ) -> Result<()> {
+        let data = self.try_data()?;
+        let shape = data.value().shape().to_vec();
+        if fits(&shape) {
+            return Ok(());
+        }
+        Err(Error::ShapeMismatch {
+            op,
+            shapes: vec![shape, args.to_vec()],
+            names: vec![data.name.clone()],
+        })
+    }
+
+    fn check_binary(
+        &self,
+        other: &Autograd<T>,
+        op: &'static str,
+        result_shape: fn(&[usize], &[usize]) -> Option<Vec<usize>>,
+    ) -> Result<()> {
+        let (lhs, rhs) = (self.try_data()?, other.try_data()?);
+        match result_shape(lhs.value().shape(), rhs.value().shape()) {
+            Some(_) => Ok(()),
+            None => Err(Error::ShapeMismatch {
+                op,
+                shapes: vec![lhs.value().shape().to_vec(), rhs.value().shape().to_vec()],
+                names: vec![lhs.name.clone(), rhs.name.clone()],
+            }),
+        }
+    }
+}
+
+// Check that the shapes of `tensors` fit together, and that there is at least one
+fn check_all<T: Element>(
+    tensors: &[Autograd<T>],
+    op: &'static str,
+    fits: impl FnOnce(&[Vec<usize>]) -> bool,
+) -> Result<()> {
+    let data = tensors
+        .iter()
+        .map(|t| t.try_data())
+        .collect::<Result<Vec<_>>>()?;
+    let shapes: Vec<Vec<usize>> = data.iter().map(|d| d.value().shape().to_vec()).collect();
+    if !shapes.is_empty() && fits(&shapes) {
+        return Ok(());
+    }
+    Err(Error::ShapeMismatch {
+        op,
+        shapes,
+        names: data.iter().map(|d| d.name.clone()).collect(),
+    })
+}
+
+// Shape two operands broadcast to, aligning trailing axes as ndarray does
+pub(crate) fn broadcast_shape(a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    let ndim = a.len().max(b.len());
+    let mut shape = vec![0; ndim];
+    for (i, dim) in shape.iter_mut().enumerate() {
+        let x = if i + a.len() >= ndim {
+            a[i + a.len() - ndim]
+        } else {
+            1
+        };
+        let y = if i + b.len() >= ndim {
+            b[i + b.len() - ndim]
+        } else {
+            1
+        };
+        *dim = match (x, y) {
+            (x, y) if x == y => x,
+            (1, y) => y,
+            (x, 1) => x,
+            _ => return None,
+        };
+    }
+    Some(shape)
+}
+
+// Shape of `a @ b` under the rules of `batched_dot`
+pub(crate) fn matmul_shape(a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let (a_batch, a_mat) = a.split_at(a.len() - 2);
+    let (b_batch, b_mat) = b.split_at(b.len() - 2);
+    if a_mat[1] != b_mat[0] || (!b_batch.is_empty() && a_batch != b_batch) {
+        return None;
+    }
+    let mut shape = a_batch.to_vec();
+    shape.extend_from_slice(&[a_mat[0], b_mat[1]]);
+    Some(shape)
+}
@@ -0,0 +1,127 @@
+use ndarray::{ArrayD, Axis, IxDyn, Slice};
+
+use crate::autograd::{
+    Element, Op, batched_dot, broadcast_zip, checked::broadcast_shape, gelu_inner, keepdims_shape,
+    logsumexp, reshape, sigmoid,
+};
+
+impl<T: Element> Op<T> {
+    // Forward computation of the op from the values of its inputs, shared by
+    // every engine that executes ops
+    pub(super) fn eval(&self, inputs: &[&ArrayD<T>]) -> ArrayD<T> {
+        let x = inputs[0];
+        match self {
+            Op::Add => x + inputs[1],
+            Op::Sub => x - inputs[1],
+            Op::Mul => x * inputs[1],
+            Op::Div => x / inputs[1],
+            Op::MatMul => batched_dot(x, inputs[1]),
+            // The exponent is a 0-dimensional constant input
+            Op::Pow => {
+                let power = inputs[1].sum();
+                x.mapv(|x| x.powf(power))
+            }
+            Op::Neg => x.mapv(|x| -x),
+            Op::Log => x.mapv(|x| x.max(T::epsilon()).ln()),
+            Op::Exp => x.mapv(|x| x.exp()),
+            Op::Tanh => x.mapv(|x| x.tanh()),
+            Op::ReLU => x.mapv(|x| x.max(T::zero())),
+            Op::Sigmoid => x.mapv(sigmoid),
+            Op::Gelu => {
+                let half = T::from_f64(0.5).unwrap();
+                x.mapv(|x| half * x * (T::one() + gelu_inner(x).tanh()))
+            }
+            Op::SiLU => x.mapv(|x| x * sigmoid(x)),
+            Op::LeakyReLU(slope) => x.mapv(|x| if x > T::zero() { x } else { *slope * x }),
+            Op::Elu(alpha) => x.mapv(|x| {
+                if x > T::zero() {
+                    x
+                } else {
+                    *alpha * x.exp_m1()
+                }
+            }),
+            Op::Softplus => x.mapv(|x| x.max(T::zero()) + (-x.abs()).exp().ln_1p()),
+            Op::Sqrt => x.mapv(|x| x.sqrt()),
+            Op::Abs => x.mapv(|x| x.abs()),
+            Op::Sin => x.mapv(|x| x.sin()),
+            Op::Cos => x.mapv(|x| x.cos()),
+            Op::Clamp { min, max } => x.mapv(|x| x.max(*min).min(*max)),
+            Op::Minimum => broadcast_zip(x, inputs[1], T::min),
+            Op::Maximum => broadcast_zip(x, inputs[1], T::max),
+            Op::Where(cond) => {
+                let b = inputs[1];
+                let shape = broadcast_shape(cond.shape(), x.shape())
+                    .and_then(|shape| broadcast_shape(&shape, b.shape()))
+                    .expect("where_ got shapes that do not broadcast together");
+                ndarray::Zip::from(&cond.broadcast(shape.clone()).unwrap())
+                    .and(&x.broadcast(shape.clone()).unwrap())
+                    .and(&b.broadcast(shape).unwrap())
+                    .map_collect(|&c, &x, &y| if c { x } else { y })
+            }
+            Op::StopGradient => x.clone(),
+            Op::Sum { axis, keepdims }
+            | Op::Mean { axis, keepdims }
+            | Op::Max { axis, keepdims }
+            | Op::Min { axis, keepdims } => self.reduce(x, *axis, *keepdims),
+            Op::LogSumExp { axis, keepdims } => {
+                let lse = logsumexp(x, *axis);
+                match (keepdims, axis) {
+                    (true, _) => lse,
+                    (false, Some(axis)) => lse.index_axis_move(Axis(*axis), 0),
+                    (false, None) => reshape(&lse, IxDyn(&[])),
+                }
+            }
+            Op::Softmax(axis) => (x - &logsumexp(x, Some(*axis))).mapv(|x| x.exp()),
+            Op::LogSoftmax(axis) => x - &logsumexp(x, Some(*axis)),
+            Op::Reshape(shape) => reshape(x, IxDyn(shape)),
+            Op::Permute(axes) => x
+                .view()
+                .permuted_axes(axes.as_slice())
+                .as_standard_layout()
+                .into_owned(),
+            Op::Concat(axis) => {
+                let views: Vec<_> = inputs.iter().map(|x| x.view()).collect();
+                ndarray::concatenate(Axis(*axis), &views)
+                    .expect("concat got shapes that do not fit together")
+            }
+            Op::Stack(axis) => {
+                let views: Vec<_> = inputs.iter().map(|x| x.view()).collect();
+                ndarray::stack(Axis(*axis), &views).expect("stack got shapes that differ")
+            }
+            Op::Slice { axis, start, end } => x
+                .slice_axis(Axis(*axis), Slice::from(*start..*end))
+                .to_owned(),
+            Op::Custom(function) => function.forward(inputs),
+            Op::Released | Op::None => unreachable!("leaves are not computed from inputs"),
+        }
+    }
+
+    fn reduce(&self, input: &ArrayD<T>, axis: Option<usize>, keepdims: bool) -> ArrayD<T> {
+        let fold = |acc: T, x: &T| match self {
+            Op::Max { .. } => acc.max(*x),
+            Op::Min { .. } => acc.min(*x),
+            _ => acc + *x,
+        };
+        let init = match self {
+            Op::Max { .. } => T::neg_infinity(),
+            Op::Min { .. } => T::infinity(),
+            _ => T::zero(),
+        };
+        let count = match axis {
+            Some(axis) => input.shape()[axis],
+            None => input.len(),
+        };
+
+        let mut reduced = match axis {
+            Some(axis) => input.fold_axis(Axis(axis), init, |acc, x| fold(*acc, x)),
+            None => ArrayD::from_elem(IxDyn(&[]), input.iter().fold(init, fold)),
+        };
+        if let Op::Mean { .. } = self {
+            reduced.mapv_inplace(|x| x / T::from_usize(count).unwrap());
+        }
+        if keepdims {
+            reduced = reshape(&reduced, keepdims_shape(input.shape(), axis));
+        }
+        reduced
+    }
+}
@@ -0,0 +1,268 @@
+use ndarray::{Array2, ArrayD, Axis, IxDyn, Slice};
+use std::collections::HashMap;
+
+use crate::autograd::backward::vjp;
+use crate::autograd::{
+    Autograd, Element, Op, batched_dot, call_on_leaves, extremum_mask, keepdims_shape, reshape,
+    select_weights,
+};
+use crate::error::{Error, Result};
+
+/// Evaluate `f` at `primals` and its directional derivative along `tangents`,
+/// i.e. the Jacobian-vector product, in a single forward sweep. Returns the
+/// value and the tangent of the output.
+///
+/// `f` is traced once on fresh leaves, so the caller's tensors are not touched.
+pub fn jvp<T, F>(
+    f: F,
+    primals: &[ArrayD<T>],
+    tangents: &[ArrayD<T>],
+) -> Result<(ArrayD<T>, ArrayD<T>)>
+where
+    T: Element,
+    F: Fn(&[Autograd<T>]) -> Autograd<T>,
+{
+    assert_eq!(
+        primals.len(),
+        tangents.len(),
+        "jvp needs one tangent per primal"
+    );
+    for (primal, tangent) in primals.iter().zip(tangents) {
+        if primal.shape() != tangent.shape() {
+            return Err(Error::GradShape {
+                expected: primal.shape().to_vec(),
+                got: tangent.shape().to_vec(),
+            });
+        }
+    }
+
+    let (leaves, output) = call_on_leaves(&f, primals);
+    let seeds: Vec<(&Autograd<T>, &ArrayD<T>)> = leaves.iter().zip(tangents).collect();
+    let tangent = push_forward(&output, &seeds)?;
+    Ok((output.value(), tangent))
+}
+
+/// Jacobian of `f` with respect to each of `primals`, computed in forward mode
+/// with one sweep per input element. The Jacobian for an input has the shape of
+/// the output followed by the shape of that input.
+///
+/// This is the cheap direction when `f` has few inputs and many outputs.
+pub fn jacfwd<T, F>(f: F, primals: &[ArrayD<T>]) -> Result<Vec<ArrayD<T>>>
+where
+    T: Element,
+    F: Fn(&[Autograd<T>]) -> Autograd<T>,
+{
+    // The graph is traced once and only the tangents are recomputed per column
+    let (leaves, output) = call_on_leaves(&f, primals);
+    let out_shape = output.value().shape().to_vec();
+    let out_len: usize = out_shape.iter().product();
+
+    let mut jacobians = Vec::with_capacity(primals.len());
+    for (leaf, primal) in leaves.iter().zip(primals) {
+        let mut jacobian = Array2::zeros((out_len, primal.len()));
+        let mut basis = ArrayD::zeros(primal.raw_dim());
+        for (column, index) in primal.indexed_iter().map(|(index, _)| index).enumerate() {
+            basis[&index] = T::one();
+            let tangent = push_forward(&output, &[(leaf, &basis)])?;
+            basis[&index] = T::zero();
+
+            jacobian
+                .column_mut(column)
+                .assign(&reshape(&tangent, IxDyn(&[out_len])));
+        }
+
+        let mut shape = out_shape.clone();
+        shape.extend_from_slice(primal.shape());
+        jacobians.push(reshape(&jacobian.into_dyn(), IxDyn(&shape)));
+    }
+    Ok(jacobians)
+}
+
+// Propagate the tangents of some leaves through the graph of `output`, children
+// before parents. Nodes that depend on no seeded leaf have a zero tangent and
+// are skipped.
+fn push_forward<T: Element>(
+    output: &Autograd<T>,
+    seeds: &[(&Autograd<T>, &ArrayD<T>)],
+) -> Result<ArrayD<T>> {
+    let mut tangents: HashMap<*const (), ArrayD<T>> = HashMap::new();
+    for (leaf, tangent) in seeds {
+        tangents.insert(leaf.as_ptr(), (*tangent).clone());
+    }
+
+    for node in output.get_topo() {
+        let data = node.try_data()?;
+        if let Op::None | Op::Released = data.op {
+            continue;
+        }
+        let child_tangents: Vec<_> = data
+            .children
+            .iter()
+            .map(|c| tangents.get(&c.as_ptr()))
+            .collect();
+        if child_tangents.iter().all(Option::is_none) {
+            continue;
+        }
+
+        let tangent = {
+            let child_data = data
+                .children
+                .iter()
+                .map(|c| c.try_data())
+                .collect::<Result<Vec<_>>>()?;
+            let values: Vec<_> = child_data.iter().map(|d| d.value()).collect();
+            let inputs: Vec<_> = values.iter().map(|v| &**v).collect();
+            jvp_rule(&data.op, &inputs, &data.value(), &child_tangents)?
+        };
+        tangents.insert(node.as_ptr(), tangent);
+    }
+
+    match tangents.remove(&output.as_ptr()) {
+        Some(tangent) => Ok(tangent),
+        None => Ok(ArrayD::zeros(output.try_data()?.value().raw_dim())),
+    }
+}
+
+// Tangent of the output of `op` given the tangent of each input. `None` stands
+// for a zero tangent.
+fn jvp_rule<T: Element>(
+    op: &Op<T>,
+    inputs: &[&ArrayD<T>],
+    output: &ArrayD<T>,
+    tangents: &[Option<&ArrayD<T>>],
+) -> Result<ArrayD<T>> {
+    let t = |i: usize| match tangents[i] {
+        Some(t) => t.clone(),
+        None => ArrayD::zeros(inputs[i].raw_dim()),
+    };
+    // Sum over `axis` (or everything) and drop into the output's shape
+    let reduce = |x: &ArrayD<T>, axis: Option<usize>| {
+        let summed = match axis {
+            Some(axis) => x.sum_axis(Axis(axis)),
+            None => ndarray::arr0(x.sum()).into_dyn(),
+        };
+        reshape(&summed, output.raw_dim())
+    };
+
+    let tangent = match op {
+        // y = a + b -> dy = da + db
+        Op::Add => t(0) + t(1),
+        // y = a - b -> dy = da - db
+        Op::Sub => t(0) - t(1),
+        // y = a * b -> dy = da * b + a * db
+        Op::Mul => t(0) * inputs[1] + inputs[0] * &t(1),
+        // y = a @ b -> dy = da @ b + a @ db
+        Op::MatMul => batched_dot(&t(0), inputs[1]) + batched_dot(inputs[0], &t(1)),
+        // y = a / b -> dy = da / b - a * db / b^2
+        Op::Div => {
+            let (a, b) = (inputs[0], inputs[1]);
+            t(0) / b - a * &t(1) / &b.mapv(|x| x * x)
+        }
+        // Elementwise ops have a diagonal Jacobian, so the tangent rule is the
+        // same as the gradient rule
+        Op::Neg
+        | Op::Pow
+        | Op::Log
+        | Op::Exp
+        | Op::Tanh
+        | Op::ReLU
+        | Op::Sigmoid
+        | Op::Gelu
+        | Op::SiLU
+        | Op::LeakyReLU(_)
+        | Op::Elu(_)
+        | Op::Softplus
+        | Op::Sqrt
+        | Op::Abs
+        | Op::Sin
+        | Op::Cos
+        | Op::Clamp { .. } => vjp(op, inputs, output, &t(0), &[true])?
+            .swap_remove(0)
+            .unwrap(),
+        Op::StopGradient => ArrayD::zeros(output.raw_dim()),
+        // y = sum(x) -> dy = sum(dx)
+        Op::Sum { axis, .. } => reduce(&t(0), *axis),
+        Op::Mean { axis, .. } => {
+            let count = match axis {
+                Some(axis) => inputs[0].shape()[*axis],
+                None => inputs[0].len(),
+            };
+            reduce(&t(0), *axis).mapv(|x| x / T::from_usize(count).unwrap())
+        }
+        // y = max(x) -> dy = dx at the (first) argmax
+        Op::Max { axis, .. } | Op::Min { axis, .. } => {
+            reduce(&(extremum_mask(inputs[0], output, *axis) * &t(0)), *axis)
+        }
+        // y = logsumexp(x) -> dy = sum(softmax(x) * dx)
+        Op::LogSumExp { axis, .. } => {
+            let keepdims = keepdims_shape(inputs[0].shape(), *axis);
+            let softmax = (inputs[0] - &reshape(output, keepdims)).mapv(|x| x.exp());
+            reduce(&(softmax * &t(0)), *axis)
+        }
+        // y = softmax(x) -> dy = y * (dx - sum(y * dx))
+        Op::Softmax(axis) => {
+            let dx = t(0);
+            let dot = (output * &dx)
+                .sum_axis(Axis(*axis))
+                .insert_axis(Axis(*axis));
+            output * &(dx - &dot)
+        }
+        // y = log_softmax(x) -> dy = dx - sum(softmax(x) * dx)
+        Op::LogSoftmax(axis) => {
+            let dx = t(0);
+            let dot = (output.mapv(|y| y.exp()) * &dx)
+                .sum_axis(Axis(*axis))
+                .insert_axis(Axis(*axis));
+            dx - &dot
+        }
+        // Shape ops move the tangent exactly as they move the value
+        Op::Reshape(_) => reshape(&t(0), output.raw_dim()),
+        Op::Permute(axes) => t(0)
+            .permuted_axes(axes.clone())
+            .as_standard_layout()
+            .to_owned(),
+        Op::Concat(axis) => {
+            let parts: Vec<_> = (0..inputs.len()).map(t).collect();
+            let views: Vec<_> = parts.iter().map(|p| p.view()).collect();
+            ndarray::concatenate(Axis(*axis), &views).unwrap()
+        }
+        Op::Stack(axis) => {
+            let parts: Vec<_> = (0..inputs.len()).map(t).collect();
+            let views: Vec<_> = parts.iter().map(|p| p.view()).collect();
+            ndarray::stack(Axis(*axis), &views).unwrap()
+        }
+        Op::Slice { axis, start, end } => t(0)
+            .slice_axis(Axis(*axis), Slice::from(*start..*end))
+            .to_owned(),
+        // y = min(a, b) -> dy = da where a < b, db where b < a
+        Op::Minimum | Op::Maximum => {
+            let weight = select_weights(inputs[0], inputs[1], matches!(op, Op::Minimum));
+            let other = weight.mapv(|w| T::one() - w);
+            weight * &t(0) + other * &t(1)
+        }
+        // y = cond ? a : b -> dy = cond ? da : db
+        Op::Where(cond) => {
+            let mask = cond.broadcast(output.raw_dim()).unwrap();
+            let selected = mask.mapv(|c| if c { T::one() } else { T::zero() });
+            let other = mask.mapv(|c| if c { T::zero() } else { T::one() });
+            selected * &t(0) + other * &t(1)
+        }
+        Op::Custom(function) => {
+            let parts: Vec<_> = (0..inputs.len()).map(t).collect();
+            let parts: Vec<_> = parts.iter().collect();
+            function
+                .jvp(inputs, output, &parts)
+                .ok_or_else(|| Error::NoJvp {
+                    op: function.name().to_string(),
+                })?
+        }
+        Op::Released | Op::None => unreachable!("leaves have no tangent rule"),
+    };
+
+    // Operands that were broadcast leave their tangent in the smaller shape
+    if tangent.shape() == output.shape() {
+        Ok(tangent)
+    } else {
+        Ok(tangent.broadcast(output.raw_dim()).unwrap().to_owned())
+    }
+}
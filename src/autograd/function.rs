@@ -0,0 +1,44 @@
+use ndarray::ArrayD;
+
+use crate::autograd::Element;
+
+/// A differentiable operation defined outside the crate.
+///
+/// Implement `forward` and `backward` over plain arrays and pass the function to
+/// [`Autograd::apply`](crate::autograd::Autograd::apply) to use it inside a graph.
+pub trait Function<T: Element = f64> {
+    /// Name shown by `Autograd::op` and the graph visualizer.
+    fn name(&self) -> &str;
+
+    /// Compute the output from the input values.
+    fn forward(&self, inputs: &[&ArrayD<T>]) -> ArrayD<T>;
+
+    /// Given the input values, the forward output and the gradient of the loss
+    /// with respect to that output, return the gradient for each input, in order
+    /// and with the same shape as the input. Backward passes fail with an error
+    /// otherwise.
+    fn backward(
+        &self,
+        inputs: &[&ArrayD<T>],
+        output: &ArrayD<T>,
+        grad: &ArrayD<T>,
+    ) -> Vec<ArrayD<T>>;
+
+    /// Given the input values, the forward output and a tangent for each input,
+    /// return the tangent of the output. Only forward-mode differentiation
+    /// (`jvp`, `jacfwd`) needs this; the default reports it as unsupported.
+    fn jvp(
+        &self,
+        _inputs: &[&ArrayD<T>],
+        _output: &ArrayD<T>,
+        _tangents: &[&ArrayD<T>],
+    ) -> Option<ArrayD<T>> {
+        None
+    }
+}
+
+impl<T: Element> std::fmt::Debug for dyn Function<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
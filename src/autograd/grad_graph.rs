@@ -0,0 +1,366 @@
+use ndarray::{ArrayD, Dimension, IxDyn};
+use std::collections::HashMap;
+
+use crate::autograd::backward::{check_graph, check_root, custom_backward};
+use crate::autograd::{
+    Autograd, Element, Op, extremum_mask, inverse_permutation, keepdims_shape, select_weights,
+};
+use crate::error::Result;
+
+impl<T: Element> Autograd<T> {
+    /// Like `backward`, but builds the gradients themselves as graph nodes so that
+    /// they can be differentiated again, e.g. for Hessian-vector products or
+    /// gradient penalties. Each node's gradient node is available from
+    /// `grad_node`, and `grad` is updated as usual.
+    ///
+    /// Gradients returned by custom `Function`s enter the new graph as constants.
+    /// A gradient node refers back to the graph it was computed from, so call
+    /// `zero_grad` on the inputs once it is no longer needed to release it.
+    pub fn backward_create_graph(&self) -> Result<()> {
+        let topo = self.get_topo();
+        check_graph(self, &topo)?;
+        let seed = self.seed_ones()?;
+        let mut grads = self.grad_graph(&topo, seed)?;
+
+        for node in &topo {
+            let Some(g) = grads.remove(&node.as_ptr()) else {
+                continue;
+            };
+            let mut data = node.data.borrow_mut();
+            // The root's grad is the seed itself, as in `backward`
+            if node.as_ptr() == self.as_ptr() {
+                data.grad = Some(g.value());
+            } else {
+                data.accumulate_grad(&g.value());
+            }
+            data.grad_node = Some(match data.grad_node.take() {
+                Some(prev) => prev.add(&g),
+                None => g,
+            });
+        }
+        Ok(())
+    }
+
+    // Gradient nodes of this single-element node with respect to `inputs`, or
+    // `None` for inputs it does not depend on. No node's `grad` or `grad_node`
+    // is written. Released nodes count as leaves, as in `gradients`.
+    pub(crate) fn gradient_nodes(
+        &self,
+        inputs: &[Autograd<T>],
+    ) -> Result<Vec<Option<Autograd<T>>>> {
+        check_root(self)?;
+        let topo = self.get_topo();
+        let seed = self.seed_ones()?;
+        let grads = self.grad_graph(&topo, seed)?;
+        Ok(inputs
+            .iter()
+            .map(|x| grads.get(&x.as_ptr()).cloned())
+            .collect())
+    }
+
+    fn grad_graph(
+        &self,
+        topo: &[Autograd<T>],
+        seed: ArrayD<T>,
+    ) -> Result<HashMap<*const (), Autograd<T>>> {
+        let mut grads: HashMap<*const (), Autograd<T>> = HashMap::new();
+        grads.insert(self.as_ptr(), Autograd::constant(seed));
+
+        for node in topo.iter().rev() {
+            let Some(grad) = grads.get(&node.as_ptr()).cloned() else {
+                continue;
+            };
+            let (op, children) = {
+                let data = node.data.borrow();
+                (data.op.clone(), data.children.clone())
+            };
+
+            for (child, g) in children.iter().zip(vjp_graph(&op, &children, node, &grad)?) {
+                let Some(g) = g else {
+                    continue;
+                };
+                if !child.requires_grad() {
+                    continue;
+                }
+                match grads.get_mut(&child.as_ptr()) {
+                    Some(acc) => *acc = acc.add(&g),
+                    None => {
+                        grads.insert(child.as_ptr(), g);
+                    }
+                }
+            }
+        }
+        Ok(grads)
+    }
+}
+
+// Gradient of each child given the gradient of `output`, built from graph ops so
+// that it can be differentiated again. `None` marks inputs that get no gradient.
+fn vjp_graph<T: Element>(
+    op: &Op<T>,
+    children: &[Autograd<T>],
+    output: &Autograd<T>,
+    grad: &Autograd<T>,
+) -> Result<Vec<Option<Autograd<T>>>> {
+    let shape = |i: usize| children[i].data.borrow().value().shape().to_vec();
+
+    Ok(match op {
+        Op::Add => vec![Some(sum_to(grad, &shape(0))), Some(sum_to(grad, &shape(1)))],
+        Op::Sub => vec![
+            Some(sum_to(grad, &shape(0))),
+            Some(sum_to(&grad.neg(), &shape(1))),
+        ],
+        Op::Mul => {
+            let (a, b) = (&children[0], &children[1]);
+            vec![
+                Some(sum_to(&grad.mul(b), &shape(0))),
+                Some(sum_to(&grad.mul(a), &shape(1))),
+            ]
+        }
+        Op::Div => {
+            // dy/db = -a/b^2
+            let (a, b) = (&children[0], &children[1]);
+            vec![
+                Some(sum_to(&grad.div(b), &shape(0))),
+                Some(sum_to(&grad.mul(a).div(&b.mul(b)).neg(), &shape(1))),
+            ]
+        }
+        Op::MatMul => {
+            let (a, b) = (&children[0], &children[1]);
+            let (a_shape, b_shape) = (shape(0), shape(1));
+            let da = grad.matmul(&swap_last(b));
+            let db = if b_shape.len() == 2 {
+                // b was shared across the batch, so fold the batch into the rows
+                let (k, m) = (b_shape[0], b_shape[1]);
+                let rows = a_shape.iter().product::<usize>() / k;
+                a.reshape(&[rows, k])
+                    .transpose()
+                    .matmul(&grad.reshape(&[rows, m]))
+            } else {
+                swap_last(a).matmul(grad)
+            };
+            vec![Some(da), Some(db)]
+        }
+        Op::Pow => {
+            // y = x^p -> dy/dx = p * x^(p-1), the exponent is a constant
+            let power = children[1].data.borrow().value().sum();
+            let local = children[0]
+                .pow(power - T::one())
+                .mul(&Autograd::scalar(power));
+            vec![Some(grad.mul(&local)), None]
+        }
+        Op::Log => vec![Some(grad.div(&children[0]))],
+        Op::Neg => vec![Some(grad.neg())],
+        Op::Exp => vec![Some(grad.mul(output))],
+        Op::Tanh => {
+            let local = Autograd::scalar(T::one()).sub(&output.mul(output));
+            vec![Some(grad.mul(&local))]
+        }
+        Op::Sigmoid => {
+            let local = output.mul(&Autograd::scalar(T::one()).sub(output));
+            vec![Some(grad.mul(&local))]
+        }
+        Op::Gelu => {
+            let x = &children[0];
+            let half = Autograd::scalar(T::from_f64(0.5).unwrap());
+            let c = Autograd::scalar(T::from_f64((2.0 / std::f64::consts::PI).sqrt()).unwrap());
+            let k = Autograd::scalar(T::from_f64(0.044715).unwrap());
+            let one = Autograd::scalar(T::one());
+            let x2 = x.mul(x);
+            let t = c.mul(&x.add(&k.mul(&x2).mul(x))).tanh();
+            let dt = one
+                .sub(&t.mul(&t))
+                .mul(&c)
+                .mul(&one.add(&Autograd::scalar(T::from_f64(3.0).unwrap()).mul(&k).mul(&x2)));
+            let local = half.mul(&one.add(&t)).add(&half.mul(x).mul(&dt));
+            vec![Some(grad.mul(&local))]
+        }
+        Op::SiLU => {
+            let x = &children[0];
+            let s = x.sigmoid();
+            let one = Autograd::scalar(T::one());
+            let local = s.mul(&one.add(&x.mul(&one.sub(&s))));
+            vec![Some(grad.mul(&local))]
+        }
+        Op::LeakyReLU(slope) => {
+            let mask = children[0]
+                .value()
+                .mapv(|x| if x > T::zero() { T::one() } else { *slope });
+            vec![Some(grad.mul(&Autograd::constant(mask)))]
+        }
+        Op::Elu(alpha) => {
+            // 1 where x > 0, otherwise y + alpha through the output node so that
+            // the second derivative alpha * exp(x) is kept
+            let mask = children[0]
+                .value()
+                .mapv(|x| if x > T::zero() { T::one() } else { T::zero() });
+            let mask = Autograd::constant(mask);
+            let one = Autograd::scalar(T::one());
+            let negative = one.sub(&mask).mul(&output.add(&Autograd::scalar(*alpha)));
+            vec![Some(grad.mul(&mask.add(&negative)))]
+        }
+        Op::Softplus => vec![Some(grad.mul(&children[0].sigmoid()))],
+        Op::Sqrt => {
+            let two = Autograd::scalar(T::from_f64(2.0).unwrap());
+            vec![Some(grad.div(&output.mul(&two)))]
+        }
+        Op::Abs => {
+            let sign = children[0].value().mapv(|x| {
+                if x > T::zero() {
+                    T::one()
+                } else if x < T::zero() {
+                    -T::one()
+                } else {
+                    T::zero()
+                }
+            });
+            vec![Some(grad.mul(&Autograd::constant(sign)))]
+        }
+        Op::Sin => vec![Some(grad.mul(&children[0].cos()))],
+        Op::Cos => vec![Some(grad.mul(&children[0].sin().neg()))],
+        Op::Clamp { min, max } => {
+            let mask = children[0].value().mapv(|x| {
+                if x >= *min && x <= *max {
+                    T::one()
+                } else {
+                    T::zero()
+                }
+            });
+            vec![Some(grad.mul(&Autograd::constant(mask)))]
+        }
+        Op::Minimum | Op::Maximum => {
+            let weight = select_weights(
+                &children[0].value(),
+                &children[1].value(),
+                matches!(op, Op::Minimum),
+            );
+            let other = weight.mapv(|w| T::one() - w);
+            vec![
+                Some(sum_to(&grad.mul(&Autograd::constant(weight)), &shape(0))),
+                Some(sum_to(&grad.mul(&Autograd::constant(other)), &shape(1))),
+            ]
+        }
+        Op::Where(cond) => {
+            let out_shape = IxDyn(output.data.borrow().value().shape());
+            let mask = cond.broadcast(out_shape).unwrap();
+            let selected = mask.mapv(|c| if c { T::one() } else { T::zero() });
+            let other = mask.mapv(|c| if c { T::zero() } else { T::one() });
+            vec![
+                Some(sum_to(&grad.mul(&Autograd::constant(selected)), &shape(0))),
+                Some(sum_to(&grad.mul(&Autograd::constant(other)), &shape(1))),
+            ]
+        }
+        Op::StopGradient => vec![None],
+        Op::ReLU => {
+            let mask = output
+                .value()
+                .mapv(|x| if x > T::zero() { T::one() } else { T::zero() });
+            vec![Some(grad.mul(&Autograd::constant(mask)))]
+        }
+        Op::Sum { axis, .. } | Op::Mean { axis, .. } => {
+            let input_shape = shape(0);
+            let zeros = Autograd::constant(ArrayD::zeros(IxDyn(&input_shape)));
+            let mut local = grad
+                .reshape(keepdims_shape(&input_shape, *axis).slice())
+                .add(&zeros);
+            if let Op::Mean { .. } = op {
+                let count = match axis {
+                    Some(axis) => input_shape[*axis],
+                    None => input_shape.iter().product(),
+                };
+                local = local.div(&Autograd::scalar(T::from_usize(count).unwrap()));
+            }
+            vec![Some(local)]
+        }
+        Op::Max { axis, .. } | Op::Min { axis, .. } => {
+            let input_shape = shape(0);
+            let mask = extremum_mask(&children[0].value(), &output.value(), *axis);
+            let local = grad
+                .reshape(keepdims_shape(&input_shape, *axis).slice())
+                .mul(&Autograd::constant(mask));
+            vec![Some(local)]
+        }
+        Op::LogSumExp { axis, .. } => {
+            let keepdims = keepdims_shape(&shape(0), *axis);
+            let softmax = children[0].sub(&output.reshape(keepdims.slice())).exp();
+            vec![Some(softmax.mul(&grad.reshape(keepdims.slice())))]
+        }
+        Op::Softmax(axis) => {
+            let dot = grad.mul(output).sum_axis(*axis, true);
+            vec![Some(output.mul(&grad.sub(&dot)))]
+        }
+        Op::LogSoftmax(axis) => {
+            let total = grad.sum_axis(*axis, true);
+            vec![Some(grad.sub(&output.exp().mul(&total)))]
+        }
+        Op::Reshape(_) => vec![Some(grad.reshape(&shape(0)))],
+        Op::Permute(axes) => vec![Some(grad.permute(&inverse_permutation(axes)))],
+        Op::Concat(axis) => {
+            let mut start = 0;
+            (0..children.len())
+                .map(|i| {
+                    let len = shape(i)[*axis];
+                    start += len;
+                    Some(grad.slice_axis(*axis, start - len, start))
+                })
+                .collect()
+        }
+        Op::Stack(axis) => (0..children.len())
+            .map(|i| Some(grad.slice_axis(*axis, i, i + 1).reshape(&shape(i))))
+            .collect(),
+        Op::Slice { axis, start, end } => {
+            // Pad the gradient with zeros back to the input's extent along `axis`
+            let input_shape = shape(0);
+            let zeros = |len: usize| {
+                let mut zeros_shape = input_shape.clone();
+                zeros_shape[*axis] = len;
+                Autograd::constant(ArrayD::zeros(IxDyn(&zeros_shape)))
+            };
+            let mut parts = Vec::new();
+            if *start > 0 {
+                parts.push(zeros(*start));
+            }
+            parts.push(grad.clone());
+            if *end < input_shape[*axis] {
+                parts.push(zeros(input_shape[*axis] - end));
+            }
+            vec![Some(Autograd::concat(&parts, *axis))]
+        }
+        Op::Custom(function) => {
+            let inputs: Vec<_> = children.iter().map(|c| c.value()).collect();
+            let input_refs: Vec<_> = inputs.iter().collect();
+            custom_backward(
+                function.as_ref(),
+                &input_refs,
+                &output.value(),
+                &grad.value(),
+            )?
+            .into_iter()
+            .map(|g| Some(Autograd::constant(g)))
+            .collect()
+        }
+        Op::Released | Op::None => Vec::new(),
+    })
+}
+
+// Graph counterpart of `unbroadcast`
+fn sum_to<T: Element>(grad: &Autograd<T>, shape: &[usize]) -> Autograd<T> {
+    let mut reduced = grad.clone();
+    while reduced.data.borrow().value().ndim() > shape.len() {
+        reduced = reduced.sum_axis(0, false);
+    }
+    for (axis, &dim) in shape.iter().enumerate() {
+        if dim == 1 && reduced.data.borrow().value().shape()[axis] != 1 {
+            reduced = reduced.sum_axis(axis, true);
+        }
+    }
+    reduced
+}
+
+// Graph counterpart of `transpose_last`
+fn swap_last<T: Element>(a: &Autograd<T>) -> Autograd<T> {
+    let ndim = a.data.borrow().value().ndim();
+    let mut axes: Vec<usize> = (0..ndim).collect();
+    axes.swap(ndim - 2, ndim - 1);
+    a.permute(&axes)
+}
@@ -0,0 +1,81 @@
+use std::cell::Cell;
+use std::marker::PhantomData;
+
+thread_local! {
+    static GRAD_ENABLED: Cell<bool> = const { Cell::new(true) };
+    static TRACING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether ops on this thread currently record the graph needed by `backward`.
+pub fn is_grad_enabled() -> bool {
+    GRAD_ENABLED.with(|enabled| enabled.get())
+}
+
+/// Stop recording the graph on this thread until the returned guard is dropped.
+/// Ops still compute their values but return leaves without children, which
+/// makes evaluation passes cheaper:
+///
+/// ```
+/// use ndarray::array;
+/// use rust_autograd::autograd::{Autograd, no_grad};
+///
+/// let x = Autograd::new(array![[1.0]]);
+/// let y = {
+///     let _guard = no_grad();
+///     x.exp()
+/// };
+/// assert!(y.children().is_empty());
+/// ```
+pub fn no_grad() -> NoGradGuard {
+    let prev = GRAD_ENABLED.with(|enabled| enabled.replace(false));
+    NoGradGuard {
+        prev,
+        _not_send: PhantomData,
+    }
+}
+
+/// Record the graph again until the returned guard is dropped, e.g. for a part
+/// of a `no_grad` region that does need gradients.
+pub fn enable_grad() -> NoGradGuard {
+    let prev = GRAD_ENABLED.with(|enabled| enabled.replace(true));
+    NoGradGuard {
+        prev,
+        _not_send: PhantomData,
+    }
+}
+
+/// Restores the previous grad mode when dropped, so guards can be nested.
+pub struct NoGradGuard {
+    prev: bool,
+    // The mode is per thread, so the guard must be dropped where it was created
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for NoGradGuard {
+    fn drop(&mut self) {
+        GRAD_ENABLED.with(|enabled| enabled.set(self.prev));
+    }
+}
+
+// Whether `trace` is recording on this thread. Ops then keep their inputs even
+// when no gradient flows through them, so that they can be replayed.
+pub(super) fn is_tracing() -> bool {
+    TRACING.with(|tracing| tracing.get())
+}
+
+// Record every op until the returned guard is dropped
+pub(super) fn start_tracing() -> TracingGuard {
+    TracingGuard {
+        prev: TRACING.with(|tracing| tracing.replace(true)),
+    }
+}
+
+pub(super) struct TracingGuard {
+    prev: bool,
+}
+
+impl Drop for TracingGuard {
+    fn drop(&mut self) {
+        TRACING.with(|tracing| tracing.set(self.prev));
+    }
+}
@@ -0,0 +1,63 @@
+use ndarray::ArrayD;
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use crate::autograd::{Autograd, AutogradData, Element};
+use crate::error::{Error, Result};
+
+type HookFn<T> = dyn Fn(&ArrayD<T>) -> Option<ArrayD<T>>;
+pub(super) type Hook<T> = Rc<HookFn<T>>;
+
+/// Returned by `register_hook`. Dropping it keeps the hook registered.
+pub struct HookHandle<T: Element = f64> {
+    node: Weak<RefCell<AutogradData<T>>>,
+    hook: Weak<HookFn<T>>,
+}
+
+impl<T: Element> HookHandle<T> {
+    pub fn remove(self) {
+        if let Some(node) = self.node.upgrade() {
+            node.borrow_mut()
+                .hooks
+                .retain(|hook| !Weak::ptr_eq(&Rc::downgrade(hook), &self.hook));
+        }
+    }
+}
+
+impl<T: Element> Autograd<T> {
+    /// Register a hook that `backward` calls with this node's gradient once it
+    /// is complete, before it is accumulated into `grad` and propagated further.
+    /// Returning `Some` replaces the gradient, e.g. to clip it; `None` leaves it
+    /// as is. Hooks run in the order they were registered.
+    ///
+    /// Hooks are not called by `backward_create_graph`.
+    pub fn register_hook<F>(&self, hook: F) -> HookHandle<T>
+    where
+        F: Fn(&ArrayD<T>) -> Option<ArrayD<T>> + 'static,
+    {
+        let hook: Hook<T> = Rc::new(hook);
+        let handle = HookHandle {
+            node: Rc::downgrade(&self.data),
+            hook: Rc::downgrade(&hook),
+        };
+        self.data.borrow_mut().hooks.push(hook);
+        handle
+    }
+
+    pub(super) fn run_hooks(&self, mut grad: ArrayD<T>) -> Result<ArrayD<T>> {
+        // Hooks may read the node, so none of its data is borrowed while they run
+        let hooks = self.try_data()?.hooks.clone();
+        for hook in hooks {
+            if let Some(new_grad) = hook(&grad) {
+                if new_grad.shape() != grad.shape() {
+                    return Err(Error::GradShape {
+                        expected: grad.shape().to_vec(),
+                        got: new_grad.shape().to_vec(),
+                    });
+                }
+                grad = new_grad;
+            }
+        }
+        Ok(grad)
+    }
+}
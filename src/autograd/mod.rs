@@ -0,0 +1,786 @@
+use ndarray::{Array, ArrayD, Axis, Dimension, Ix2, IxDyn, LinalgScalar, ScalarOperand};
+use num_traits::{Float, FromPrimitive};
+use std::cell::{Ref, RefCell};
+use std::collections::HashSet;
+use std::fmt::{Debug, Display};
+use std::ops::AddAssign;
+use std::rc::Rc;
+
+use checked::broadcast_shape;
+use grad_mode::is_tracing;
+use hooks::Hook;
+
+mod backward;
+mod checked;
+mod eval;
+mod forward;
+mod function;
+mod grad_graph;
+mod grad_mode;
+mod hooks;
+mod ops;
+mod tape;
+mod trace;
+
+pub use backward::BackwardOptions;
+pub use forward::{jacfwd, jvp};
+pub use function::Function;
+pub use grad_mode::{NoGradGuard, enable_grad, is_grad_enabled, no_grad};
+pub use hooks::HookHandle;
+pub use tape::{Gradients, Tape, Var};
+pub use trace::{CompiledGraph, trace};
+
+/// Scalar type a graph can be built over, implemented for `f32` and `f64`.
+pub trait Element:
+    Float + FromPrimitive + ScalarOperand + LinalgScalar + AddAssign + Debug + Display + Default
+{
+}
+
+impl Element for f32 {}
+impl Element for f64 {}
+
+#[derive(Debug, Clone)]
+enum Op<T: Element> {
+    Add,
+    Sub,
+    Neg,
+    Mul,
+    MatMul,
+    Div,
+    Pow,
+    Log,
+    Exp,
+    Tanh,
+    ReLU,
+    Sigmoid,
+    Gelu,
+    SiLU,
+    LeakyReLU(T),
+    Elu(T),
+    Softplus,
+    Sqrt,
+    Abs,
+    Sin,
+    Cos,
+    Clamp {
+        min: T,
+        max: T,
+    },
+    Minimum,
+    Maximum,
+    Where(ArrayD<bool>),
+    StopGradient,
+    Sum {
+        axis: Option<usize>,
+        keepdims: bool,
+    },
+    Mean {
+        axis: Option<usize>,
+        keepdims: bool,
+    },
+    Max {
+        axis: Option<usize>,
+        keepdims: bool,
+    },
+    Min {
+        axis: Option<usize>,
+        keepdims: bool,
+    },
+    LogSumExp {
+        axis: Option<usize>,
+        keepdims: bool,
+    },
+    Softmax(usize),
+    LogSoftmax(usize),
+    Reshape(Vec<usize>),
+    Permute(Vec<usize>),
+    Concat(usize),
+    Stack(usize),
+    Slice {
+        axis: usize,
+        start: usize,
+        end: usize,
+    },
+    Custom(Rc<dyn Function<T>>),
+    // An interior node whose edges were freed by a non-retaining backward pass
+    Released,
+    None,
+}
+
+// Inner data structure
+struct AutogradData<T: Element> {
+    // Shared with the nodes detached from this one
+    value: Rc<RefCell<ArrayD<T>>>,
+    // Allocated by the first gradient that reaches the node
+    grad: Option<ArrayD<T>>,
+    children: Vec<Autograd<T>>,
+    op: Op<T>,
+    name: String,
+    grad_node: Option<Autograd<T>>,
+    requires_grad: bool,
+    hooks: Vec<Hook<T>>,
+}
+
+impl<T: Element> AutogradData<T> {
+    fn value(&self) -> Ref<'_, ArrayD<T>> {
+        self.value.borrow()
+    }
+
+    fn accumulate_grad(&mut self, grad: &ArrayD<T>) {
+        match &mut self.grad {
+            Some(acc) => *acc += grad,
+            slot => *slot = Some(grad.clone()),
+        }
+    }
+}
+
+// Wrapper with Rc for shared ownership
+pub struct Autograd<T: Element = f64> {
+    data: Rc<RefCell<AutogradData<T>>>,
+}
+impl<T: Element> Autograd<T> {
+    pub fn new<D: Dimension>(value: Array<T, D>) -> Self {
+        Autograd::with_value(Rc::new(RefCell::new(value.into_dyn())))
+    }
+
+    fn with_value(value: Rc<RefCell<ArrayD<T>>>) -> Self {
+        Self {
+            data: Rc::new(RefCell::new(AutogradData {
+                value,
+                grad: None,
+                children: Vec::new(),
+                op: Op::None,
+                name: String::new(),
+                grad_node: None,
+                requires_grad: true,
+                hooks: Vec::new(),
+            })),
+        }
+    }
+
+    /// A leaf that never receives a gradient, e.g. a target or a fixed input.
+    pub fn constant<D: Dimension>(value: Array<T, D>) -> Self {
+        let result = Autograd::new(value);
+        result.data.borrow_mut().requires_grad = false;
+        result
+    }
+
+    /// A 0-dimensional constant that broadcasts against any shape.
+    pub fn scalar(value: T) -> Self {
+        Autograd::constant(ndarray::arr0(value))
+    }
+
+    // Wrap the result of an op. How it was computed is only recorded if some
+    // input requires a gradient and tracking is not disabled by `no_grad`, or
+    // if `trace` is recording.
+    fn from_op(value: ArrayD<T>, children: Vec<Autograd<T>>, op: Op<T>) -> Autograd<T> {
+        let result = Autograd::new(value);
+        let requires_grad = is_grad_enabled() && children.iter().any(|c| c.requires_grad());
+        {
+            let mut data = result.data.borrow_mut();
+            data.requires_grad = requires_grad;
+            if requires_grad || is_tracing() {
+                data.children = children;
+                data.op = op;
+            }
+        }
+        result
+    }
+
+    // Evaluate `op` on the values of `children` and wrap the result
+    fn eval_op(op: Op<T>, children: Vec<Autograd<T>>) -> Autograd<T> {
+        let value = {
+            let data: Vec<_> = children.iter().map(|c| c.data.borrow()).collect();
+            let values: Vec<_> = data.iter().map(|d| d.value()).collect();
+            let values: Vec<_> = values.iter().map(|v| &**v).collect();
+            op.eval(&values)
+        };
+
+        Autograd::from_op(value, children, op)
+    }
+
+    pub fn add(&self, other: &Autograd<T>) -> Autograd<T> {
+        Autograd::eval_op(Op::Add, vec![self.clone(), other.clone()])
+    }
+
+    pub fn sub(&self, other: &Autograd<T>) -> Autograd<T> {
+        Autograd::eval_op(Op::Sub, vec![self.clone(), other.clone()])
+    }
+
+    pub fn mul(&self, other: &Autograd<T>) -> Autograd<T> {
+        Autograd::eval_op(Op::Mul, vec![self.clone(), other.clone()])
+    }
+
+    pub fn matmul(&self, other: &Autograd<T>) -> Autograd<T> {
+        Autograd::eval_op(Op::MatMul, vec![self.clone(), other.clone()])
+    }
+
+    pub fn div(&self, other: &Autograd<T>) -> Autograd<T> {
+        Autograd::eval_op(Op::Div, vec![self.clone(), other.clone()])
+    }
+
+    pub fn pow(&self, power: T) -> Autograd<T> {
+        let exponent = Autograd::scalar(power);
+
+        Autograd::eval_op(Op::Pow, vec![self.clone(), exponent])
+    }
+
+    pub fn log(&self) -> Autograd<T> {
+        Autograd::eval_op(Op::Log, vec![self.clone()])
+    }
+
+    pub fn neg(&self) -> Autograd<T> {
+        Autograd::eval_op(Op::Neg, vec![self.clone()])
+    }
+
+    pub fn exp(&self) -> Autograd<T> {
+        Autograd::eval_op(Op::Exp, vec![self.clone()])
+    }
+
+    pub fn tanh(&self) -> Autograd<T> {
+        Autograd::eval_op(Op::Tanh, vec![self.clone()])
+    }
+
+    pub fn relu(&self) -> Autograd<T> {
+        Autograd::eval_op(Op::ReLU, vec![self.clone()])
+    }
+
+    pub fn sigmoid(&self) -> Autograd<T> {
+        Autograd::eval_op(Op::Sigmoid, vec![self.clone()])
+    }
+
+    /// GELU in its usual tanh approximation,
+    /// `0.5 * x * (1 + tanh(sqrt(2 / pi) * (x + 0.044715 * x^3)))`.
+    pub fn gelu(&self) -> Autograd<T> {
+        Autograd::eval_op(Op::Gelu, vec![self.clone()])
+    }
+
+    /// SiLU, also known as swish: `x * sigmoid(x)`.
+    pub fn silu(&self) -> Autograd<T> {
+        Autograd::eval_op(Op::SiLU, vec![self.clone()])
+    }
+
+    pub fn leaky_relu(&self, slope: T) -> Autograd<T> {
+        Autograd::eval_op(Op::LeakyReLU(slope), vec![self.clone()])
+    }
+
+    pub fn elu(&self, alpha: T) -> Autograd<T> {
+        Autograd::eval_op(Op::Elu(alpha), vec![self.clone()])
+    }
+
+    /// `log(1 + exp(x))`, computed without overflow for large `x`.
+    pub fn softplus(&self) -> Autograd<T> {
+        Autograd::eval_op(Op::Softplus, vec![self.clone()])
+    }
+
+    pub fn sqrt(&self) -> Autograd<T> {
+        Autograd::eval_op(Op::Sqrt, vec![self.clone()])
+    }
+
+    pub fn abs(&self) -> Autograd<T> {
+        Autograd::eval_op(Op::Abs, vec![self.clone()])
+    }
+
+    pub fn sin(&self) -> Autograd<T> {
+        Autograd::eval_op(Op::Sin, vec![self.clone()])
+    }
+
+    pub fn cos(&self) -> Autograd<T> {
+        Autograd::eval_op(Op::Cos, vec![self.clone()])
+    }
+
+    /// Limit every element to `[min, max]`. The gradient passes through where
+    /// the input is within the bounds, inclusive, and is 0 elsewhere.
+    pub fn clamp(&self, min: T, max: T) -> Autograd<T> {
+        Autograd::eval_op(Op::Clamp { min, max }, vec![self.clone()])
+    }
+
+    /// Elementwise minimum of two tensors, broadcasting like `add`. Where they
+    /// are equal the gradient is split evenly between them.
+    pub fn minimum(&self, other: &Autograd<T>) -> Autograd<T> {
+        Autograd::eval_op(Op::Minimum, vec![self.clone(), other.clone()])
+    }
+
+    /// Elementwise maximum of two tensors, see `minimum`.
+    pub fn maximum(&self, other: &Autograd<T>) -> Autograd<T> {
+        Autograd::eval_op(Op::Maximum, vec![self.clone(), other.clone()])
+    }
+
+    /// Select from `a` where `cond` is true and from `b` elsewhere. All three
+    /// broadcast together, and each side only gets the gradient of the elements
+    /// it was selected for.
+    pub fn where_<D: Dimension>(
+        cond: &Array<bool, D>,
+        a: &Autograd<T>,
+        b: &Autograd<T>,
+    ) -> Autograd<T> {
+        let cond = cond.view().into_dyn().to_owned();
+
+        Autograd::eval_op(Op::Where(cond), vec![a.clone(), b.clone()])
+    }
+
+    /// Identity in the forward pass that passes no gradient back to `self`. A
+    /// straight-through estimator is `&x + (&q - &x).stop_gradient()`, which has
+    /// the value of `q` but the gradient of `x`.
+    pub fn stop_gradient(&self) -> Autograd<T> {
+        Autograd::eval_op(Op::StopGradient, vec![self.clone()])
+    }
+
+    /// A constant that shares this node's value but is cut off from the graph.
+    /// Gradients of anything computed from it never reach `self`, while a later
+    /// `set_value` on either node is seen by both.
+    pub fn detach(&self) -> Autograd<T> {
+        if is_tracing() {
+            // Keep the edge so that the traced graph reads the current value
+            let result = Autograd::eval_op(Op::StopGradient, vec![self.clone()]);
+            result.data.borrow_mut().requires_grad = false;
+            return result;
+        }
+        let result = Autograd::with_value(Rc::clone(&self.data.borrow().value));
+        result.data.borrow_mut().requires_grad = false;
+        result
+    }
+
+    pub fn reshape(&self, shape: &[usize]) -> Autograd<T> {
+        Autograd::eval_op(Op::Reshape(shape.to_vec()), vec![self.clone()])
+    }
+
+    /// Reverse the order of all axes, the N-dimensional counterpart of `.t()`.
+    pub fn transpose(&self) -> Autograd<T> {
+        let axes: Vec<usize> = (0..self.data.borrow().value().ndim()).rev().collect();
+        self.permute(&axes)
+    }
+
+    /// Reorder axes so that axis `i` of the result is axis `axes[i]` of `self`.
+    pub fn permute(&self, axes: &[usize]) -> Autograd<T> {
+        Autograd::eval_op(Op::Permute(axes.to_vec()), vec![self.clone()])
+    }
+
+    /// Join tensors along an existing axis.
+    pub fn concat(tensors: &[Autograd<T>], axis: usize) -> Autograd<T> {
+        Autograd::eval_op(Op::Concat(axis), tensors.to_vec())
+    }
+
+    /// Join same-shaped tensors along a new axis inserted at `axis`.
+    pub fn stack(tensors: &[Autograd<T>], axis: usize) -> Autograd<T> {
+        Autograd::eval_op(Op::Stack(axis), tensors.to_vec())
+    }
+
+    /// Take `start..end` along `axis`, e.g. a range of columns out of a batch.
+    pub fn slice_axis(&self, axis: usize, start: usize, end: usize) -> Autograd<T> {
+        Autograd::eval_op(Op::Slice { axis, start, end }, vec![self.clone()])
+    }
+
+    /// Run a user-defined [`Function`] on `inputs`, recording it in the graph so
+    /// that `backward` calls back into `Function::backward`.
+    pub fn apply<F: Function<T> + 'static>(function: F, inputs: &[Autograd<T>]) -> Autograd<T> {
+        Autograd::eval_op(Op::Custom(Rc::new(function)), inputs.to_vec())
+    }
+
+    /// Sum of all elements, as a 0-dimensional tensor.
+    pub fn sum(&self) -> Autograd<T> {
+        self.reduce(Op::Sum {
+            axis: None,
+            keepdims: false,
+        })
+    }
+
+    /// Sum along `axis`, keeping it as a length-1 axis if `keepdims` is set.
+    pub fn sum_axis(&self, axis: usize, keepdims: bool) -> Autograd<T> {
+        self.reduce(Op::Sum {
+            axis: Some(axis),
+            keepdims,
+        })
+    }
+
+    pub fn mean(&self) -> Autograd<T> {
+        self.reduce(Op::Mean {
+            axis: None,
+            keepdims: false,
+        })
+    }
+
+    pub fn mean_axis(&self, axis: usize, keepdims: bool) -> Autograd<T> {
+        self.reduce(Op::Mean {
+            axis: Some(axis),
+            keepdims,
+        })
+    }
+
+    /// Largest element. The gradient flows only to the first maximal element.
+    pub fn max(&self) -> Autograd<T> {
+        self.reduce(Op::Max {
+            axis: None,
+            keepdims: false,
+        })
+    }
+
+    pub fn max_axis(&self, axis: usize, keepdims: bool) -> Autograd<T> {
+        self.reduce(Op::Max {
+            axis: Some(axis),
+            keepdims,
+        })
+    }
+
+    /// Smallest element. The gradient flows only to the first minimal element.
+    pub fn min(&self) -> Autograd<T> {
+        self.reduce(Op::Min {
+            axis: None,
+            keepdims: false,
+        })
+    }
+
+    pub fn min_axis(&self, axis: usize, keepdims: bool) -> Autograd<T> {
+        self.reduce(Op::Min {
+            axis: Some(axis),
+            keepdims,
+        })
+    }
+
+    /// `log(sum(exp(x)))` over all elements, shifted by the maximum so that large
+    /// inputs cannot overflow.
+    pub fn logsumexp(&self) -> Autograd<T> {
+        self.reduce(Op::LogSumExp {
+            axis: None,
+            keepdims: false,
+        })
+    }
+
+    pub fn logsumexp_axis(&self, axis: usize, keepdims: bool) -> Autograd<T> {
+        self.reduce(Op::LogSumExp {
+            axis: Some(axis),
+            keepdims,
+        })
+    }
+
+    /// Softmax along `axis`, computed as `exp(x - logsumexp(x))` so that large
+    /// inputs cannot overflow.
+    pub fn softmax(&self, axis: usize) -> Autograd<T> {
+        Autograd::eval_op(Op::Softmax(axis), vec![self.clone()])
+    }
+
+    /// `log(softmax(x))` along `axis`, without ever taking the log of a
+    /// probability that underflowed to 0.
+    pub fn log_softmax(&self, axis: usize) -> Autograd<T> {
+        Autograd::eval_op(Op::LogSoftmax(axis), vec![self.clone()])
+    }
+
+    fn reduce(&self, op: Op<T>) -> Autograd<T> {
+        Autograd::eval_op(op, vec![self.clone()])
+    }
+
+    // Post-order depth-first traversal with an explicit stack, so that very deep
+    // graphs cannot overflow the call stack
+    fn build_topo(
+        &self,
+        topo: &mut Vec<Autograd<T>>,
+        visited: &mut HashSet<*const RefCell<AutogradData<T>>>,
+    ) {
+        let mut stack = vec![(self.clone(), false)];
+        while let Some((node, expanded)) = stack.pop() {
+            if expanded {
+                topo.push(node);
+                continue;
+            }
+            if !visited.insert(Rc::as_ptr(&node.data)) {
+                continue;
+            }
+            let children = node.data.borrow().children.clone();
+            stack.push((node, true));
+            stack.extend(children.into_iter().rev().map(|child| (child, false)));
+        }
+    }
+
+    pub fn zero_grad(&self) {
+        let mut data = self.data.borrow_mut();
+        data.grad = None;
+        data.grad_node = None;
+    }
+
+    pub fn value(&self) -> ArrayD<T> {
+        self.data.borrow().value().clone()
+    }
+
+    pub fn grad(&self) -> ArrayD<T> {
+        let data = self.data.borrow();
+        match &data.grad {
+            Some(grad) => grad.clone(),
+            None => ArrayD::zeros(data.value().raw_dim()),
+        }
+    }
+
+    /// Gradient as a differentiable node, set by `backward_create_graph`.
+    pub fn grad_node(&self) -> Option<Autograd<T>> {
+        self.data.borrow().grad_node.clone()
+    }
+
+    pub fn set_value<D: Dimension>(&self, value: Array<T, D>) {
+        *self.data.borrow().value.borrow_mut() = value.into_dyn();
+    }
+
+    pub fn set_grad<D: Dimension>(&self, grad: Array<T, D>) {
+        self.data.borrow_mut().grad = Some(grad.into_dyn());
+    }
+
+    pub fn requires_grad(&self) -> bool {
+        self.data.borrow().requires_grad
+    }
+
+    /// Freeze or unfreeze a node, typically a parameter. Only results computed
+    /// after the change pick it up, and `backward` leaves frozen nodes alone.
+    pub fn set_requires_grad(&self, requires_grad: bool) {
+        self.data.borrow_mut().requires_grad = requires_grad;
+    }
+
+    pub fn set_name(&self, name: &str) {
+        self.data.borrow_mut().name = name.to_string();
+    }
+
+    pub fn name(&self) -> String {
+        self.data.borrow().name.clone()
+    }
+
+    pub fn children(&self) -> Vec<Autograd<T>> {
+        self.data.borrow().children.clone()
+    }
+
+    pub fn op(&self) -> String {
+        match &self.data.borrow().op {
+            Op::Custom(function) => function.name().to_string(),
+            op => format!("{:?}", op),
+        }
+    }
+
+    pub fn get_topo(&self) -> Vec<Autograd<T>> {
+        let mut topo = Vec::new();
+        let mut visited = HashSet::new();
+        self.build_topo(&mut topo, &mut visited);
+        topo
+    }
+
+    pub fn as_ptr(&self) -> *const () {
+        Rc::as_ptr(&self.data) as *const ()
+    }
+
+    /// Copy the value into a new leaf of another precision, e.g. to deploy
+    /// f64-trained parameters as f32. The name is kept; the graph is not.
+    pub fn cast<U: Element>(&self) -> Autograd<U> {
+        let data = self.data.borrow();
+        let result = Autograd::new(data.value().mapv(|x| U::from(x).unwrap()));
+        result.set_name(&data.name);
+        result.set_requires_grad(data.requires_grad);
+        result
+    }
+}
+
+// Call `f` on fresh leaves holding copies of `primals`, recording the graph even
+// inside `no_grad`. Transforms that take a function of arrays build on this.
+pub(crate) fn call_on_leaves<T, F>(f: &F, primals: &[ArrayD<T>]) -> (Vec<Autograd<T>>, Autograd<T>)
+where
+    T: Element,
+    F: Fn(&[Autograd<T>]) -> Autograd<T>,
+{
+    let _guard = enable_grad();
+    let leaves: Vec<Autograd<T>> = primals.iter().map(|p| Autograd::new(p.clone())).collect();
+    let output = f(&leaves);
+    (leaves, output)
+}
+
+// Reshape in logical (row-major) order regardless of the memory layout
+fn reshape<T: Element>(a: &ArrayD<T>, shape: IxDyn) -> ArrayD<T> {
+    a.to_shape(shape).unwrap().into_owned()
+}
+
+// Logistic function, split by sign so that `exp` never overflows
+fn sigmoid<T: Element>(x: T) -> T {
+    if x >= T::zero() {
+        T::one() / (T::one() + (-x).exp())
+    } else {
+        let e = x.exp();
+        e / (T::one() + e)
+    }
+}
+
+// log(sum(exp(x))) over `axis`, or everything, with the reduced axes kept.
+// Shifting by the maximum keeps every `exp` at most 1.
+fn logsumexp<T: Element>(x: &ArrayD<T>, axis: Option<usize>) -> ArrayD<T> {
+    let max = match axis {
+        Some(axis) => x.fold_axis(Axis(axis), T::neg_infinity(), |m, v| m.max(*v)),
+        None => ndarray::arr0(x.fold(T::neg_infinity(), |m, v| m.max(*v))).into_dyn(),
+    };
+    // A lane of -inf (or inf) has nothing to shift by
+    let max = reshape(&max, keepdims_shape(x.shape(), axis))
+        .mapv(|m| if m.is_finite() { m } else { T::zero() });
+
+    let exps = (x - &max).mapv(|v| v.exp());
+    let sums = match axis {
+        Some(axis) => exps.sum_axis(Axis(axis)),
+        None => ndarray::arr0(exps.sum()).into_dyn(),
+    };
+    max + reshape(&sums, keepdims_shape(x.shape(), axis)).mapv(|s| s.ln())
+}
+
+// Apply `f` elementwise to two arrays broadcast against each other
+fn broadcast_zip<T: Element>(a: &ArrayD<T>, b: &ArrayD<T>, f: impl Fn(T, T) -> T) -> ArrayD<T> {
+    let shape = broadcast_shape(a.shape(), b.shape()).unwrap_or_else(|| {
+        panic!(
+            "shapes {:?} and {:?} do not broadcast together",
+            a.shape(),
+            b.shape()
+        )
+    });
+    ndarray::Zip::from(&a.broadcast(shape.clone()).unwrap())
+        .and(&b.broadcast(shape).unwrap())
+        .map_collect(|&x, &y| f(x, y))
+}
+
+// Share of the gradient of an elementwise minimum (or maximum, with `less` set
+// to false) that goes to `a`, in the broadcast shape. Ties are split evenly.
+fn select_weights<T: Element>(a: &ArrayD<T>, b: &ArrayD<T>, less: bool) -> ArrayD<T> {
+    let half = T::from_f64(0.5).unwrap();
+    broadcast_zip(a, b, |x, y| {
+        if x == y {
+            half
+        } else if (x < y) == less {
+            T::one()
+        } else {
+            T::zero()
+        }
+    })
+}
+
+// Argument of `tanh` in the GELU approximation
+fn gelu_inner<T: Element>(x: T) -> T {
+    let c = T::from_f64((2.0 / std::f64::consts::PI).sqrt()).unwrap();
+    c * (x + T::from_f64(0.044715).unwrap() * x * x * x)
+}
+
+fn inverse_permutation(axes: &[usize]) -> Vec<usize> {
+    let mut inverse = vec![0; axes.len()];
+    for (i, &axis) in axes.iter().enumerate() {
+        inverse[axis] = i;
+    }
+    inverse
+}
+
+// Shape of a reduction result when the reduced axes are kept with length 1
+fn keepdims_shape(shape: &[usize], axis: Option<usize>) -> IxDyn {
+    match axis {
+        Some(axis) => {
+            let mut kept = shape.to_vec();
+            kept[axis] = 1;
+            IxDyn(&kept)
+        }
+        None => IxDyn(&vec![1; shape.len()]),
+    }
+}
+
+// One-hot mask of the first element in each reduced lane that equals the
+// reduction result, i.e. where a max/min reduction takes its value from
+fn extremum_mask<T: Element>(
+    input: &ArrayD<T>,
+    value: &ArrayD<T>,
+    axis: Option<usize>,
+) -> ArrayD<T> {
+    let mut mask = ArrayD::zeros(input.raw_dim());
+
+    // Flatten everything into a single lane when reducing the whole tensor
+    let (input, value, lane_axis) = match axis {
+        Some(axis) => (
+            input.clone(),
+            reshape(value, keepdims_shape(input.shape(), Some(axis))),
+            Axis(axis),
+        ),
+        None => (
+            reshape(input, IxDyn(&[input.len()])),
+            reshape(value, IxDyn(&[1])),
+            Axis(0),
+        ),
+    };
+    let mut mask_lanes = mask.view_mut().into_shape(input.raw_dim()).unwrap();
+    for ((lane, mut mask_lane), m) in input
+        .lanes(lane_axis)
+        .into_iter()
+        .zip(mask_lanes.lanes_mut(lane_axis))
+        .zip(value.iter())
+    {
+        if let Some(i) = lane.iter().position(|x| x == m) {
+            mask_lane[i] = T::one();
+        }
+    }
+    mask
+}
+
+// Matrix product over the last two axes. Leading axes are batch axes and must
+// match, except that a 2-D right-hand side is shared across the whole batch.
+fn batched_dot<T: Element>(a: &ArrayD<T>, b: &ArrayD<T>) -> ArrayD<T> {
+    assert!(
+        a.ndim() >= 2 && b.ndim() >= 2,
+        "matmul needs operands with at least 2 dimensions, got {:?} and {:?}",
+        a.shape(),
+        b.shape()
+    );
+    let (n, k) = (a.shape()[a.ndim() - 2], a.shape()[a.ndim() - 1]);
+    let m = b.shape()[b.ndim() - 1];
+    let mut out_shape = a.shape()[..a.ndim() - 2].to_vec();
+    out_shape.extend_from_slice(&[n, m]);
+
+    if b.ndim() == 2 {
+        let a2 = a.to_shape((a.len() / k, k)).unwrap();
+        let b2 = b.view().into_dimensionality::<Ix2>().unwrap();
+        return a2.dot(&b2).into_shape(IxDyn(&out_shape)).unwrap();
+    }
+
+    assert_eq!(
+        a.shape()[..a.ndim() - 2],
+        b.shape()[..b.ndim() - 2],
+        "matmul batch dimensions must match"
+    );
+    let batch = a.len() / (n * k);
+    let a3 = a.to_shape((batch, n, k)).unwrap();
+    let b3 = b.to_shape((batch, k, m)).unwrap();
+    let mut out = ndarray::Array3::zeros((batch, n, m));
+    for i in 0..batch {
+        out.index_axis_mut(Axis(0), i)
+            .assign(&a3.index_axis(Axis(0), i).dot(&b3.index_axis(Axis(0), i)));
+    }
+    out.into_shape(IxDyn(&out_shape)).unwrap()
+}
+
+// Dropping the root of a long chain would otherwise recurse through every
+// `children` vector. Nodes this one owns exclusively are unlinked onto a stack
+// instead and dropped one at a time.
+impl<T: Element> Drop for AutogradData<T> {
+    fn drop(&mut self) {
+        let mut stack = std::mem::take(&mut self.children);
+        stack.extend(self.grad_node.take());
+        while let Some(node) = stack.pop() {
+            if let Ok(cell) = Rc::try_unwrap(node.data) {
+                let mut data = cell.into_inner();
+                stack.append(&mut data.children);
+                stack.extend(data.grad_node.take());
+            }
+        }
+    }
+}
+
+impl<T: Element> Clone for Autograd<T> {
+    fn clone(&self) -> Self {
+        Self {
+            data: Rc::clone(&self.data),
+        }
+    }
+}
+
+impl<T: Element> std::fmt::Debug for Autograd<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let data = self.data.borrow();
+        f.debug_struct("Autograd")
+            .field("name", &data.name)
+            .field("value", &*data.value())
+            .field("grad", &self.grad())
+            .field("children", &data.children)
+            .field("op", &data.op)
+            .finish()
+    }
+}
@@ -0,0 +1,108 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::autograd::{Autograd, Element};
+
+// `a op b` for every combination of owned and borrowed tensors, plus a scalar on
+// the right. Scalars become 0-dimensional constant leaves that broadcast.
+macro_rules! impl_binary_op {
+    ($trait:ident, $method:ident) => {
+        impl<T: Element> $trait<&Autograd<T>> for &Autograd<T> {
+            type Output = Autograd<T>;
+
+            fn $method(self, rhs: &Autograd<T>) -> Autograd<T> {
+                Autograd::$method(self, rhs)
+            }
+        }
+
+        impl<T: Element> $trait<Autograd<T>> for &Autograd<T> {
+            type Output = Autograd<T>;
+
+            fn $method(self, rhs: Autograd<T>) -> Autograd<T> {
+                Autograd::$method(self, &rhs)
+            }
+        }
+
+        impl<T: Element> $trait<&Autograd<T>> for Autograd<T> {
+            type Output = Autograd<T>;
+
+            fn $method(self, rhs: &Autograd<T>) -> Autograd<T> {
+                Autograd::$method(&self, rhs)
+            }
+        }
+
+        impl<T: Element> $trait<Autograd<T>> for Autograd<T> {
+            type Output = Autograd<T>;
+
+            fn $method(self, rhs: Autograd<T>) -> Autograd<T> {
+                Autograd::$method(&self, &rhs)
+            }
+        }
+
+        impl<T: Element> $trait<T> for &Autograd<T> {
+            type Output = Autograd<T>;
+
+            fn $method(self, rhs: T) -> Autograd<T> {
+                Autograd::$method(self, &Autograd::scalar(rhs))
+            }
+        }
+
+        impl<T: Element> $trait<T> for Autograd<T> {
+            type Output = Autograd<T>;
+
+            fn $method(self, rhs: T) -> Autograd<T> {
+                Autograd::$method(&self, &Autograd::scalar(rhs))
+            }
+        }
+    };
+}
+
+// `scalar op a`. Coherence rules out a generic impl, so spell out each float type.
+macro_rules! impl_scalar_lhs_op {
+    ($scalar:ty, $trait:ident, $method:ident) => {
+        impl $trait<&Autograd<$scalar>> for $scalar {
+            type Output = Autograd<$scalar>;
+
+            fn $method(self, rhs: &Autograd<$scalar>) -> Autograd<$scalar> {
+                Autograd::$method(&Autograd::scalar(self), rhs)
+            }
+        }
+
+        impl $trait<Autograd<$scalar>> for $scalar {
+            type Output = Autograd<$scalar>;
+
+            fn $method(self, rhs: Autograd<$scalar>) -> Autograd<$scalar> {
+                Autograd::$method(&Autograd::scalar(self), &rhs)
+            }
+        }
+    };
+}
+
+impl_binary_op!(Add, add);
+impl_binary_op!(Sub, sub);
+impl_binary_op!(Mul, mul);
+impl_binary_op!(Div, div);
+
+impl_scalar_lhs_op!(f32, Add, add);
+impl_scalar_lhs_op!(f32, Sub, sub);
+impl_scalar_lhs_op!(f32, Mul, mul);
+impl_scalar_lhs_op!(f32, Div, div);
+impl_scalar_lhs_op!(f64, Add, add);
+impl_scalar_lhs_op!(f64, Sub, sub);
+impl_scalar_lhs_op!(f64, Mul, mul);
+impl_scalar_lhs_op!(f64, Div, div);
+
+impl<T: Element> Neg for &Autograd<T> {
+    type Output = Autograd<T>;
+
+    fn neg(self) -> Autograd<T> {
+        Autograd::neg(self)
+    }
+}
+
+impl<T: Element> Neg for Autograd<T> {
+    type Output = Autograd<T>;
+
+    fn neg(self) -> Autograd<T> {
+        Autograd::neg(&self)
+    }
+}
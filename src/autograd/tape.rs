@@ -0,0 +1,507 @@
+use ndarray::{Array, ArrayD, Dimension};
+use std::ops::{Index, Range};
+use std::rc::Rc;
+
+use crate::autograd::backward::vjp;
+use crate::autograd::{Element, Function, Op};
+use crate::error::{Error, Result};
+
+/// Handle to a node on a [`Tape`], valid until the tape is cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Var(usize);
+
+impl Var {
+    /// Position of the node on its tape.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+struct Node<T: Element> {
+    value: ArrayD<T>,
+    op: Op<T>,
+    // Range of this node's inputs in `Tape::edges`
+    inputs: Range<usize>,
+    requires_grad: bool,
+}
+
+/// A graph stored as a flat arena instead of linked `Autograd` nodes.
+///
+/// Every op appends a node to the tape and returns its [`Var`]. Nodes only ever
+/// refer to earlier ones, so the tape is already in topological order and
+/// `backward` is a single reverse sweep that reads values in place. Leaves can
+/// be given new values with `set_value` and the tape re-run with `forward`, or
+/// `clear` empties the tape but keeps its storage for the next step:
+///
+/// ```
+/// use ndarray::array;
+/// use rust_autograd::autograd::Tape;
+///
+/// let mut tape = Tape::new();
+/// let x = tape.leaf(array![1.0, 2.0]);
+/// let y = tape.mul(x, x);
+/// let loss = tape.sum(y);
+/// let grads = tape.backward(loss).unwrap();
+/// assert_eq!(grads[x], array![2.0, 4.0].into_dyn());
+/// ```
+pub struct Tape<T: Element = f64> {
+    nodes: Vec<Node<T>>,
+    edges: Vec<usize>,
+}
+
+impl<T: Element> Default for Tape<T> {
+    fn default() -> Self {
+        Tape::new()
+    }
+}
+
+impl<T: Element> Tape<T> {
+    pub fn new() -> Self {
+        Tape {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// Drop all nodes, keeping the allocated storage.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.edges.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// An input that receives a gradient.
+    pub fn leaf<D: Dimension>(&mut self, value: Array<T, D>) -> Var {
+        self.push_leaf(value.into_dyn(), true)
+    }
+
+    /// An input that never receives a gradient.
+    pub fn constant<D: Dimension>(&mut self, value: Array<T, D>) -> Var {
+        self.push_leaf(value.into_dyn(), false)
+    }
+
+    pub fn value(&self, var: Var) -> &ArrayD<T> {
+        &self.nodes[var.0].value
+    }
+
+    /// Overwrite the value of a leaf, e.g. with updated parameters. Nodes
+    /// computed from it keep their old values until `forward` is called.
+    pub fn set_value<D: Dimension>(&mut self, var: Var, value: Array<T, D>) {
+        *self.leaf_value_mut(var) = value.into_dyn();
+    }
+
+    pub(super) fn leaf_value_mut(&mut self, var: Var) -> &mut ArrayD<T> {
+        let node = &mut self.nodes[var.0];
+        assert!(
+            matches!(node.op, Op::None),
+            "node #{} is computed from other nodes, only leaves can be set",
+            var.0
+        );
+        &mut node.value
+    }
+
+    /// Recompute every node from the current values of the leaves, in tape
+    /// order, e.g. after `set_value`.
+    pub fn forward(&mut self) {
+        for i in 0..self.nodes.len() {
+            // Inputs always sit before the node, so their values can be read
+            // while its own is replaced
+            let (before, rest) = self.nodes.split_at_mut(i);
+            let node = &mut rest[0];
+            if let Op::None = node.op {
+                continue;
+            }
+            let values: Vec<_> = self.edges[node.inputs.clone()]
+                .iter()
+                .map(|&j| &before[j].value)
+                .collect();
+            node.value = node.op.eval(&values);
+        }
+    }
+
+    pub(super) fn push_leaf(&mut self, value: ArrayD<T>, requires_grad: bool) -> Var {
+        let start = self.edges.len();
+        self.nodes.push(Node {
+            value,
+            op: Op::None,
+            inputs: start..start,
+            requires_grad,
+        });
+        Var(self.nodes.len() - 1)
+    }
+
+    fn push(&mut self, op: Op<T>, inputs: &[Var]) -> Var {
+        let value = {
+            let values: Vec<_> = inputs.iter().map(|v| &self.nodes[v.0].value).collect();
+            op.eval(&values)
+        };
+        let requires_grad = inputs.iter().any(|v| self.nodes[v.0].requires_grad);
+        self.push_node(op, inputs, value, requires_grad)
+    }
+
+    // Append a node whose value was already computed
+    pub(super) fn push_node(
+        &mut self,
+        op: Op<T>,
+        inputs: &[Var],
+        value: ArrayD<T>,
+        requires_grad: bool,
+    ) -> Var {
+        let start = self.edges.len();
+        self.edges.extend(inputs.iter().map(|v| v.0));
+        self.nodes.push(Node {
+            value,
+            op,
+            inputs: start..self.edges.len(),
+            requires_grad,
+        });
+        Var(self.nodes.len() - 1)
+    }
+
+    pub fn add(&mut self, a: Var, b: Var) -> Var {
+        self.push(Op::Add, &[a, b])
+    }
+
+    pub fn sub(&mut self, a: Var, b: Var) -> Var {
+        self.push(Op::Sub, &[a, b])
+    }
+
+    pub fn mul(&mut self, a: Var, b: Var) -> Var {
+        self.push(Op::Mul, &[a, b])
+    }
+
+    pub fn div(&mut self, a: Var, b: Var) -> Var {
+        self.push(Op::Div, &[a, b])
+    }
+
+    pub fn matmul(&mut self, a: Var, b: Var) -> Var {
+        self.push(Op::MatMul, &[a, b])
+    }
+
+    pub fn pow(&mut self, x: Var, power: T) -> Var {
+        let exponent = self.constant(ndarray::arr0(power));
+        self.push(Op::Pow, &[x, exponent])
+    }
+
+    pub fn neg(&mut self, x: Var) -> Var {
+        self.push(Op::Neg, &[x])
+    }
+
+    pub fn log(&mut self, x: Var) -> Var {
+        self.push(Op::Log, &[x])
+    }
+
+    pub fn exp(&mut self, x: Var) -> Var {
+        self.push(Op::Exp, &[x])
+    }
+
+    pub fn tanh(&mut self, x: Var) -> Var {
+        self.push(Op::Tanh, &[x])
+    }
+
+    pub fn relu(&mut self, x: Var) -> Var {
+        self.push(Op::ReLU, &[x])
+    }
+
+    pub fn sigmoid(&mut self, x: Var) -> Var {
+        self.push(Op::Sigmoid, &[x])
+    }
+
+    pub fn gelu(&mut self, x: Var) -> Var {
+        self.push(Op::Gelu, &[x])
+    }
+
+    pub fn silu(&mut self, x: Var) -> Var {
+        self.push(Op::SiLU, &[x])
+    }
+
+    pub fn sqrt(&mut self, x: Var) -> Var {
+        self.push(Op::Sqrt, &[x])
+    }
+
+    pub fn abs(&mut self, x: Var) -> Var {
+        self.push(Op::Abs, &[x])
+    }
+
+    pub fn leaky_relu(&mut self, x: Var, slope: T) -> Var {
+        self.push(Op::LeakyReLU(slope), &[x])
+    }
+
+    pub fn elu(&mut self, x: Var, alpha: T) -> Var {
+        self.push(Op::Elu(alpha), &[x])
+    }
+
+    pub fn softplus(&mut self, x: Var) -> Var {
+        self.push(Op::Softplus, &[x])
+    }
+
+    pub fn sin(&mut self, x: Var) -> Var {
+        self.push(Op::Sin, &[x])
+    }
+
+    pub fn cos(&mut self, x: Var) -> Var {
+        self.push(Op::Cos, &[x])
+    }
+
+    pub fn clamp(&mut self, x: Var, min: T, max: T) -> Var {
+        self.push(Op::Clamp { min, max }, &[x])
+    }
+
+    pub fn minimum(&mut self, a: Var, b: Var) -> Var {
+        self.push(Op::Minimum, &[a, b])
+    }
+
+    pub fn maximum(&mut self, a: Var, b: Var) -> Var {
+        self.push(Op::Maximum, &[a, b])
+    }
+
+    /// See `Autograd::where_`.
+    pub fn where_<D: Dimension>(&mut self, cond: &Array<bool, D>, a: Var, b: Var) -> Var {
+        let cond = cond.view().into_dyn().to_owned();
+        self.push(Op::Where(cond), &[a, b])
+    }
+
+    pub fn stop_gradient(&mut self, x: Var) -> Var {
+        self.push(Op::StopGradient, &[x])
+    }
+
+    pub fn sum(&mut self, x: Var) -> Var {
+        self.push(
+            Op::Sum {
+                axis: None,
+                keepdims: false,
+            },
+            &[x],
+        )
+    }
+
+    pub fn sum_axis(&mut self, x: Var, axis: usize, keepdims: bool) -> Var {
+        self.push(
+            Op::Sum {
+                axis: Some(axis),
+                keepdims,
+            },
+            &[x],
+        )
+    }
+
+    pub fn mean(&mut self, x: Var) -> Var {
+        self.push(
+            Op::Mean {
+                axis: None,
+                keepdims: false,
+            },
+            &[x],
+        )
+    }
+
+    pub fn mean_axis(&mut self, x: Var, axis: usize, keepdims: bool) -> Var {
+        self.push(
+            Op::Mean {
+                axis: Some(axis),
+                keepdims,
+            },
+            &[x],
+        )
+    }
+
+    pub fn max(&mut self, x: Var) -> Var {
+        self.push(
+            Op::Max {
+                axis: None,
+                keepdims: false,
+            },
+            &[x],
+        )
+    }
+
+    pub fn max_axis(&mut self, x: Var, axis: usize, keepdims: bool) -> Var {
+        self.push(
+            Op::Max {
+                axis: Some(axis),
+                keepdims,
+            },
+            &[x],
+        )
+    }
+
+    pub fn min(&mut self, x: Var) -> Var {
+        self.push(
+            Op::Min {
+                axis: None,
+                keepdims: false,
+            },
+            &[x],
+        )
+    }
+
+    pub fn min_axis(&mut self, x: Var, axis: usize, keepdims: bool) -> Var {
+        self.push(
+            Op::Min {
+                axis: Some(axis),
+                keepdims,
+            },
+            &[x],
+        )
+    }
+
+    pub fn logsumexp(&mut self, x: Var) -> Var {
+        self.push(
+            Op::LogSumExp {
+                axis: None,
+                keepdims: false,
+            },
+            &[x],
+        )
+    }
+
+    pub fn logsumexp_axis(&mut self, x: Var, axis: usize, keepdims: bool) -> Var {
+        self.push(
+            Op::LogSumExp {
+                axis: Some(axis),
+                keepdims,
+            },
+            &[x],
+        )
+    }
+
+    pub fn softmax(&mut self, x: Var, axis: usize) -> Var {
+        self.push(Op::Softmax(axis), &[x])
+    }
+
+    pub fn log_softmax(&mut self, x: Var, axis: usize) -> Var {
+        self.push(Op::LogSoftmax(axis), &[x])
+    }
+
+    pub fn reshape(&mut self, x: Var, shape: &[usize]) -> Var {
+        self.push(Op::Reshape(shape.to_vec()), &[x])
+    }
+
+    pub fn permute(&mut self, x: Var, axes: &[usize]) -> Var {
+        self.push(Op::Permute(axes.to_vec()), &[x])
+    }
+
+    /// Reverse the order of all axes.
+    pub fn transpose(&mut self, x: Var) -> Var {
+        let axes: Vec<usize> = (0..self.value(x).ndim()).rev().collect();
+        self.permute(x, &axes)
+    }
+
+    pub fn concat(&mut self, xs: &[Var], axis: usize) -> Var {
+        self.push(Op::Concat(axis), xs)
+    }
+
+    pub fn stack(&mut self, xs: &[Var], axis: usize) -> Var {
+        self.push(Op::Stack(axis), xs)
+    }
+
+    pub fn slice_axis(&mut self, x: Var, axis: usize, start: usize, end: usize) -> Var {
+        self.push(Op::Slice { axis, start, end }, &[x])
+    }
+
+    /// Run a user-defined [`Function`] on `inputs`.
+    pub fn apply<F: Function<T> + 'static>(&mut self, function: F, inputs: &[Var]) -> Var {
+        self.push(Op::Custom(Rc::new(function)), inputs)
+    }
+
+    /// Gradients of the single-element `root` with respect to every node it
+    /// depends on. The tape is left intact, so it can be differentiated again.
+    pub fn backward(&self, root: Var) -> Result<Gradients<T>> {
+        let shape = self.value(root).shape();
+        if self.value(root).len() != 1 {
+            return Err(Error::NonScalarRoot {
+                shape: shape.to_vec(),
+            });
+        }
+        self.backward_with(root, &ArrayD::ones(shape))
+    }
+
+    /// Like `backward`, seeding `root` with `grad` instead of ones.
+    pub fn backward_with<D: Dimension>(
+        &self,
+        root: Var,
+        grad: &Array<T, D>,
+    ) -> Result<Gradients<T>> {
+        let grad = grad.clone().into_dyn();
+        if grad.shape() != self.value(root).shape() {
+            return Err(Error::GradShape {
+                expected: self.value(root).shape().to_vec(),
+                got: grad.shape().to_vec(),
+            });
+        }
+        if !self.nodes[root.0].requires_grad {
+            return Err(Error::NoGrad {
+                name: format!("#{}", root.0),
+            });
+        }
+
+        // Nodes after the root cannot contribute to it
+        let mut grads: Vec<Option<ArrayD<T>>> = Vec::with_capacity(root.0 + 1);
+        grads.resize_with(root.0 + 1, || None);
+        grads[root.0] = Some(grad);
+
+        for i in (0..=root.0).rev() {
+            let node = &self.nodes[i];
+            if let Op::None = node.op {
+                continue;
+            }
+            // Inputs always sit before the node, so its own gradient can be
+            // borrowed while theirs are accumulated
+            let (before, rest) = grads.split_at_mut(i);
+            let Some(grad) = &rest[0] else {
+                continue;
+            };
+
+            let inputs = &self.edges[node.inputs.clone()];
+            let values: Vec<_> = inputs.iter().map(|&j| &self.nodes[j].value).collect();
+            let needs: Vec<_> = inputs
+                .iter()
+                .map(|&j| self.nodes[j].requires_grad)
+                .collect();
+            let input_grads = vjp(&node.op, &values, &node.value, grad, &needs)?;
+
+            for (&j, g) in inputs.iter().zip(input_grads) {
+                let Some(g) = g else {
+                    continue;
+                };
+                match &mut before[j] {
+                    Some(acc) => *acc += &g,
+                    slot => *slot = Some(g),
+                }
+            }
+        }
+        Ok(Gradients { grads })
+    }
+}
+
+/// Result of [`Tape::backward`], indexed by [`Var`].
+pub struct Gradients<T: Element = f64> {
+    grads: Vec<Option<ArrayD<T>>>,
+}
+
+impl<T: Element> Gradients<T> {
+    /// Gradient of `var`, or `None` if the root does not depend on it.
+    pub fn get(&self, var: Var) -> Option<&ArrayD<T>> {
+        self.grads.get(var.0).and_then(Option::as_ref)
+    }
+
+    pub(super) fn take(&mut self, var: Var) -> Option<ArrayD<T>> {
+        self.grads.get_mut(var.0).and_then(Option::take)
+    }
+}
+
+impl<T: Element> Index<Var> for Gradients<T> {
+    type Output = ArrayD<T>;
+
+    fn index(&self, var: Var) -> &ArrayD<T> {
+        self.get(var)
+            .unwrap_or_else(|| panic!("no gradient for node #{}", var.0))
+    }
+}
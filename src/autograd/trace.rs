@@ -0,0 +1,166 @@
+use ndarray::ArrayD;
+use std::collections::{HashMap, HashSet};
+
+use crate::autograd::grad_mode::start_tracing;
+use crate::autograd::{Autograd, Element, Op, Tape, Var, call_on_leaves};
+use crate::error::{Error, Result};
+
+/// A function recorded once by [`trace`] and re-executed on new inputs.
+///
+/// The nodes, their edges and the order to run them in are fixed when tracing;
+/// `forward` and `backward` only recompute values and gradients. Tensors the
+/// function captured, such as parameters, are read again on every `forward` and
+/// receive their gradients in `.grad` on `backward`, just like with
+/// `Autograd::backward`.
+///
+/// The graph is static: branches taken while tracing are baked in, and
+/// anything computed outside `Autograd` ops, such as the condition of `where_`,
+/// keeps its traced value.
+pub struct CompiledGraph<T: Element = f64> {
+    tape: Tape<T>,
+    // Leaf of each input
+    inputs: Vec<Var>,
+    // Leaves that are not inputs, with the tensor they are read from
+    captured: Vec<(Var, Autograd<T>)>,
+    output: Var,
+}
+
+/// Record `f` on leaves holding `inputs` into a [`CompiledGraph`]. Later calls
+/// must pass inputs of the same shapes.
+///
+/// ```
+/// use ndarray::{ArrayD, array};
+/// use rust_autograd::autograd::{Autograd, trace};
+///
+/// let w = Autograd::new(array![2.0, 3.0]);
+/// let mut graph = trace(|x: &[Autograd]| x[0].mul(&w).sum(), &[ArrayD::zeros(vec![2])]);
+///
+/// let y = graph.forward(&[array![1.0, 1.0].into_dyn()]).unwrap();
+/// assert_eq!(y, &ndarray::arr0(5.0).into_dyn());
+/// let grads = graph.backward().unwrap();
+/// assert_eq!(grads[0], array![2.0, 3.0].into_dyn());
+/// assert_eq!(w.grad(), array![1.0, 1.0].into_dyn());
+/// ```
+pub fn trace<T, F>(f: F, inputs: &[ArrayD<T>]) -> CompiledGraph<T>
+where
+    T: Element,
+    F: Fn(&[Autograd<T>]) -> Autograd<T>,
+{
+    let (leaves, output) = {
+        let _guard = start_tracing();
+        call_on_leaves(&f, inputs)
+    };
+
+    // The traced nodes are copied onto a tape in topological order. Inputs the
+    // output does not use go first, so that every input has a node.
+    let mut order: Vec<Autograd<T>> = Vec::new();
+    let topo = output.get_topo();
+    let used: HashSet<*const ()> = topo.iter().map(|node| node.as_ptr()).collect();
+    order.extend(
+        leaves
+            .iter()
+            .filter(|l| !used.contains(&l.as_ptr()))
+            .cloned(),
+    );
+    order.extend(topo);
+
+    let is_input: HashSet<*const ()> = leaves.iter().map(|l| l.as_ptr()).collect();
+    let mut tape = Tape::new();
+    let mut vars: HashMap<*const (), Var> = HashMap::new();
+    let mut captured = Vec::new();
+    for node in &order {
+        let data = node.data.borrow();
+        // A node whose edges a backward pass already released is as good as a
+        // leaf holding its last value
+        let var = if let Op::None | Op::Released = data.op {
+            let var = tape.push_leaf(data.value().clone(), data.requires_grad);
+            if !is_input.contains(&node.as_ptr()) {
+                captured.push((var, node.clone()));
+            }
+            var
+        } else {
+            let inputs: Vec<Var> = data.children.iter().map(|c| vars[&c.as_ptr()]).collect();
+            tape.push_node(
+                data.op.clone(),
+                &inputs,
+                data.value().clone(),
+                data.requires_grad,
+            )
+        };
+        vars.insert(node.as_ptr(), var);
+    }
+
+    CompiledGraph {
+        inputs: leaves.iter().map(|l| vars[&l.as_ptr()]).collect(),
+        output: vars[&output.as_ptr()],
+        tape,
+        captured,
+    }
+}
+
+impl<T: Element> CompiledGraph<T> {
+    /// Number of nodes in the graph.
+    pub fn len(&self) -> usize {
+        self.tape.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tape.is_empty()
+    }
+
+    /// Output of the last `forward`, or of the traced call before any.
+    pub fn output(&self) -> &ArrayD<T> {
+        self.tape.value(self.output)
+    }
+
+    /// Run the graph on new inputs, in the order `f` received them.
+    pub fn forward(&mut self, inputs: &[ArrayD<T>]) -> Result<&ArrayD<T>> {
+        assert_eq!(
+            inputs.len(),
+            self.inputs.len(),
+            "the graph was traced with {} inputs",
+            self.inputs.len()
+        );
+        for (&var, input) in self.inputs.iter().zip(inputs) {
+            let value = self.tape.leaf_value_mut(var);
+            if value.shape() != input.shape() {
+                return Err(Error::ShapeMismatch {
+                    op: "trace",
+                    shapes: vec![value.shape().to_vec(), input.shape().to_vec()],
+                    names: Vec::new(),
+                });
+            }
+            value.assign(input);
+        }
+        for (var, tensor) in &self.captured {
+            self.tape
+                .leaf_value_mut(*var)
+                .clone_from(&tensor.try_data()?.value());
+        }
+
+        self.tape.forward();
+        Ok(self.output())
+    }
+
+    /// Backpropagate from the single-element output of the last `forward`.
+    /// Returns the gradient for each input and adds those of captured tensors
+    /// to their `.grad`.
+    pub fn backward(&mut self) -> Result<Vec<ArrayD<T>>> {
+        let mut grads = self.tape.backward(self.output)?;
+
+        for (var, tensor) in &self.captured {
+            if let Some(g) = grads.take(*var) {
+                tensor.try_data_mut()?.accumulate_grad(&g);
+            }
+        }
+        Ok(self
+            .inputs
+            .iter()
+            .map(|&var| {
+                grads
+                    .take(var)
+                    .unwrap_or_else(|| ArrayD::zeros(self.tape.value(var).raw_dim()))
+            })
+            .collect())
+    }
+}
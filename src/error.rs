@@ -0,0 +1,75 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// `backward` can only seed a gradient of ones for a single-element root.
+    NonScalarRoot { shape: Vec<usize> },
+    /// The gradient given to `backward_with` does not match the root's shape.
+    GradShape {
+        expected: Vec<usize>,
+        got: Vec<usize>,
+    },
+    /// The operands of `op` have incompatible shapes. `names` holds the names
+    /// of the input nodes, empty for unnamed ones.
+    ShapeMismatch {
+        op: &'static str,
+        shapes: Vec<Vec<usize>>,
+        names: Vec<String>,
+    },
+    /// A node's data was already mutably borrowed, e.g. by a custom function
+    /// that touches the graph while it is being evaluated.
+    Borrowed { name: String },
+    /// `backward` was called on a node that does not require a gradient.
+    NoGrad { name: String },
+    /// Forward mode reached a custom function that does not implement `jvp`.
+    NoJvp { op: String },
+    /// The graph was already freed by a `backward` pass without `retain_graph`.
+    GraphReleased { name: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NonScalarRoot { shape } => write!(
+                f,
+                "backward() needs a single-element root, got shape {:?}; use backward_with to pass the upstream gradient",
+                shape
+            ),
+            Error::GradShape { expected, got } => write!(
+                f,
+                "gradient of shape {:?} does not match root of shape {:?}",
+                got, expected
+            ),
+            Error::ShapeMismatch { op, shapes, names } => {
+                write!(f, "{} got incompatible shapes", op)?;
+                for (i, shape) in shapes.iter().enumerate() {
+                    let sep = if i == 0 { " " } else { " and " };
+                    match names.get(i).filter(|name| !name.is_empty()) {
+                        Some(name) => write!(f, "{}{:?} ({})", sep, shape, name)?,
+                        None => write!(f, "{}{:?}", sep, shape)?,
+                    }
+                }
+                Ok(())
+            }
+            Error::Borrowed { name } if name.is_empty() => {
+                write!(f, "node is already borrowed")
+            }
+            Error::Borrowed { name } => write!(f, "node {} is already borrowed", name),
+            Error::NoGrad { name } => write!(
+                f,
+                "backward from node {:?}, which does not require a gradient",
+                name
+            ),
+            Error::NoJvp { op } => write!(f, "{} does not support forward mode (no jvp)", op),
+            Error::GraphReleased { name } => write!(
+                f,
+                "backward through a released graph (at node {:?}); pass retain_graph to backpropagate more than once",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
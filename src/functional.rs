@@ -0,0 +1,185 @@
+//! Derivatives of functions of plain arrays, without managing graphs by hand.
+//!
+//! Every transform evaluates `f` on fresh leaves holding copies of the given
+//! values, so no caller-held tensor or `.grad` field is touched. Forward-mode
+//! `jvp` and `jacfwd` are re-exported here as well.
+
+use ndarray::{ArrayD, Axis, IxDyn, Slice};
+
+use crate::autograd::{Autograd, Element, call_on_leaves, enable_grad};
+pub use crate::autograd::{jacfwd, jvp};
+use crate::error::Result;
+
+/// `grad(f)` is a function returning the gradient of `f` with respect to each of
+/// its inputs. `f` must return a single-element tensor.
+pub fn grad<T, F>(f: F) -> impl Fn(&[ArrayD<T>]) -> Result<Vec<ArrayD<T>>>
+where
+    T: Element,
+    F: Fn(&[Autograd<T>]) -> Autograd<T>,
+{
+    let value_and_grad = value_and_grad(f);
+    move |primals| value_and_grad(primals).map(|(_, grads)| grads)
+}
+
+// The value of a function together with its gradient for each input
+type ValueAndGrad<T> = (ArrayD<T>, Vec<ArrayD<T>>);
+
+/// Like `grad`, but the returned function also returns the value of `f`.
+pub fn value_and_grad<T, F>(f: F) -> impl Fn(&[ArrayD<T>]) -> Result<ValueAndGrad<T>>
+where
+    T: Element,
+    F: Fn(&[Autograd<T>]) -> Autograd<T>,
+{
+    move |primals| {
+        let (leaves, output) = call_on_leaves(&f, primals);
+        let grads = gradients(&output, output.seed_ones()?, &leaves)?;
+        Ok((output.value(), grads))
+    }
+}
+
+/// Jacobian of `f` with respect to each of `primals`, computed in reverse mode
+/// with one backward pass per output element. The Jacobian for an input has the
+/// shape of the output followed by the shape of that input.
+///
+/// This is the cheap direction when `f` has many inputs and few outputs; see
+/// `jacfwd` for the opposite case.
+pub fn jacobian<T, F>(f: F, primals: &[ArrayD<T>]) -> Result<Vec<ArrayD<T>>>
+where
+    T: Element,
+    F: Fn(&[Autograd<T>]) -> Autograd<T>,
+{
+    let (leaves, output) = call_on_leaves(&f, primals);
+    jacobian_of(&output, &leaves)
+}
+
+/// Hessian of the single-element function `f`. Block `[i][j]` holds the second
+/// derivatives with respect to inputs `i` and `j`, with the shape of input `i`
+/// followed by the shape of input `j`.
+pub fn hessian<T, F>(f: F, primals: &[ArrayD<T>]) -> Result<Vec<Vec<ArrayD<T>>>>
+where
+    T: Element,
+    F: Fn(&[Autograd<T>]) -> Autograd<T>,
+{
+    let (leaves, output) = call_on_leaves(&f, primals);
+    let grads = gradient_nodes(&output, &leaves)?;
+
+    // Differentiate all gradients at once as a single flat vector
+    let rows = {
+        let _guard = enable_grad();
+        let flat: Vec<Autograd<T>> = grads
+            .iter()
+            .map(|g| g.reshape(&[g.value().len()]))
+            .collect();
+        Autograd::concat(&flat, 0)
+    };
+    let jacobians = jacobian_of(&rows, &leaves)?;
+
+    let mut hessian = Vec::with_capacity(primals.len());
+    let mut start = 0;
+    for primal_i in primals {
+        let block_row = jacobians
+            .iter()
+            .zip(primals)
+            .map(|(jacobian, primal_j)| {
+                let rows = jacobian.slice_axis(Axis(0), Slice::from(start..start + primal_i.len()));
+                let mut shape = primal_i.shape().to_vec();
+                shape.extend_from_slice(primal_j.shape());
+                rows.to_shape(IxDyn(&shape)).unwrap().into_owned()
+            })
+            .collect();
+        hessian.push(block_row);
+        start += primal_i.len();
+    }
+    Ok(hessian)
+}
+
+/// Hessian-vector product of the single-element function `f`: the directional
+/// derivative of its gradient along `vectors`, one per input, without ever
+/// forming the Hessian.
+pub fn hvp<T, F>(f: F, primals: &[ArrayD<T>], vectors: &[ArrayD<T>]) -> Result<Vec<ArrayD<T>>>
+where
+    T: Element,
+    F: Fn(&[Autograd<T>]) -> Autograd<T>,
+{
+    assert_eq!(
+        primals.len(),
+        vectors.len(),
+        "hvp needs one vector per primal"
+    );
+    let (leaves, output) = call_on_leaves(&f, primals);
+    let grads = gradient_nodes(&output, &leaves)?;
+
+    // d/dx <grad f(x), v> = H v
+    let dot = {
+        let _guard = enable_grad();
+        grads
+            .iter()
+            .zip(vectors)
+            .map(|(g, v)| g.mul(&Autograd::constant(v.clone())).sum())
+            .reduce(|acc, term| acc.add(&term))
+    };
+    match dot {
+        Some(dot) => gradients(&dot, dot.seed_ones()?, &leaves),
+        None => Ok(Vec::new()),
+    }
+}
+
+// Gradients of `output` with respect to `leaves` for the given seed. A constant
+// output has no graph to walk and a zero gradient.
+fn gradients<T: Element>(
+    output: &Autograd<T>,
+    seed: ArrayD<T>,
+    leaves: &[Autograd<T>],
+) -> Result<Vec<ArrayD<T>>> {
+    if output.requires_grad() {
+        output.gradients(seed, leaves)
+    } else {
+        Ok(leaves
+            .iter()
+            .map(|l| ArrayD::zeros(l.value().raw_dim()))
+            .collect())
+    }
+}
+
+// Gradients of `output` with respect to `leaves` as differentiable graph nodes
+fn gradient_nodes<T: Element>(
+    output: &Autograd<T>,
+    leaves: &[Autograd<T>],
+) -> Result<Vec<Autograd<T>>> {
+    let _guard = enable_grad();
+    let grads = if output.requires_grad() {
+        output.gradient_nodes(leaves)?
+    } else {
+        output.seed_ones()?;
+        vec![None; leaves.len()]
+    };
+    Ok(grads
+        .into_iter()
+        .zip(leaves)
+        .map(|(g, l)| g.unwrap_or_else(|| Autograd::constant(ArrayD::zeros(l.value().raw_dim()))))
+        .collect())
+}
+
+// Reverse-mode Jacobian of `output` with respect to `leaves`, one backward pass
+// per output element
+fn jacobian_of<T: Element>(output: &Autograd<T>, leaves: &[Autograd<T>]) -> Result<Vec<ArrayD<T>>> {
+    let value = output.value();
+    let mut rows: Vec<Vec<T>> = vec![Vec::new(); leaves.len()];
+    for (index, _) in value.indexed_iter() {
+        let mut seed = ArrayD::zeros(value.raw_dim());
+        seed[&index] = T::one();
+        for (row, grad) in rows.iter_mut().zip(gradients(output, seed, leaves)?) {
+            row.extend(grad.iter());
+        }
+    }
+
+    Ok(rows
+        .into_iter()
+        .zip(leaves)
+        .map(|(row, leaf)| {
+            let mut shape = value.shape().to_vec();
+            shape.extend_from_slice(leaf.value().shape());
+            ArrayD::from_shape_vec(IxDyn(&shape), row).unwrap()
+        })
+        .collect())
+}
@@ -0,0 +1,172 @@
+use crate::autograd::{Autograd, Element, call_on_leaves, no_grad};
+use crate::error::Result;
+use ndarray::{ArrayD, Dimension};
+use std::fmt;
+
+/// Compares the gradients computed by `backward` with central finite differences.
+///
+/// The default step and tolerances suit `f64`; [`GradCheck::for_element`] picks
+/// ones that suit the element type instead.
+pub struct GradCheck {
+    pub eps: f64,
+    pub atol: f64,
+    pub rtol: f64,
+}
+
+impl Default for GradCheck {
+    fn default() -> Self {
+        Self {
+            eps: 1e-6,
+            atol: 1e-5,
+            rtol: 1e-3,
+        }
+    }
+}
+
+impl GradCheck {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defaults for elements of type `T`. With `f32` the differences are taken
+    /// over a larger step and compared more loosely, since rounding errors of
+    /// about 1e-7 swamp a step of 1e-6.
+    pub fn for_element<T: Element>() -> Self {
+        if T::epsilon().to_f64().unwrap() < 1e-10 {
+            return Self::default();
+        }
+        Self {
+            eps: 1e-2,
+            atol: 1e-3,
+            rtol: 1e-2,
+        }
+    }
+
+    pub fn eps(mut self, eps: f64) -> Self {
+        self.eps = eps;
+        self
+    }
+
+    pub fn atol(mut self, atol: f64) -> Self {
+        self.atol = atol;
+        self
+    }
+
+    pub fn rtol(mut self, rtol: f64) -> Self {
+        self.rtol = rtol;
+        self
+    }
+
+    /// Check `f` at the values of `inputs`. `f` must return a single-element
+    /// tensor. The inputs themselves are left untouched: `f` is evaluated on
+    /// fresh leaves holding copies of their values, with gradients recorded even
+    /// inside `no_grad`. An output that does not depend on the inputs has zero
+    /// analytic gradients; one with more than one element is an error.
+    pub fn check<T, F>(&self, f: F, inputs: &[Autograd<T>]) -> Result<GradCheckReport>
+    where
+        T: Element,
+        F: Fn(&[Autograd<T>]) -> Autograd<T>,
+    {
+        let values: Vec<ArrayD<T>> = inputs.iter().map(|x| x.value()).collect();
+
+        let (leaves, output) = call_on_leaves(&f, &values);
+        if output.requires_grad() {
+            output.backward()?;
+        } else {
+            output.seed_ones()?;
+        }
+
+        let eval = |values: &[ArrayD<T>]| -> f64 {
+            let _guard = no_grad();
+            let leaves: Vec<Autograd<T>> =
+                values.iter().map(|v| Autograd::new(v.clone())).collect();
+            f(&leaves).value().sum().to_f64().unwrap()
+        };
+        let eps = T::from_f64(self.eps).unwrap();
+
+        let mut report = GradCheckReport {
+            mismatches: Vec::new(),
+            max_abs_error: 0.0,
+        };
+        let mut perturbed = values.clone();
+        for (input, leaf) in leaves.iter().enumerate() {
+            let analytic = leaf.grad();
+            for (index, &original) in values[input].indexed_iter() {
+                perturbed[input][&index] = original + eps;
+                let plus = eval(&perturbed);
+                perturbed[input][&index] = original - eps;
+                let minus = eval(&perturbed);
+                perturbed[input][&index] = original;
+
+                // Divide by the step actually taken, after rounding to `T`
+                let step = ((original + eps) - (original - eps)).to_f64().unwrap();
+                let numeric = (plus - minus) / step;
+                let analytic = analytic[&index].to_f64().unwrap();
+                let error = (analytic - numeric).abs();
+
+                report.max_abs_error = report.max_abs_error.max(error);
+                if error > self.atol + self.rtol * numeric.abs() || error.is_nan() {
+                    report.mismatches.push(Mismatch {
+                        input,
+                        index: index.slice().to_vec(),
+                        analytic,
+                        numeric,
+                    });
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Run [`GradCheck`] with the defaults for the element type of `inputs`.
+pub fn gradcheck<T, F>(f: F, inputs: &[Autograd<T>]) -> Result<GradCheckReport>
+where
+    T: Element,
+    F: Fn(&[Autograd<T>]) -> Autograd<T>,
+{
+    GradCheck::for_element::<T>().check(f, inputs)
+}
+
+/// An element whose analytic and numeric gradients disagree.
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    /// Position of the input in the slice passed to `check`.
+    pub input: usize,
+    pub index: Vec<usize>,
+    pub analytic: f64,
+    pub numeric: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct GradCheckReport {
+    pub mismatches: Vec<Mismatch>,
+    pub max_abs_error: f64,
+}
+
+impl GradCheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+impl fmt::Display for GradCheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return write!(
+                f,
+                "gradcheck passed (max abs error {:e})",
+                self.max_abs_error
+            );
+        }
+        writeln!(f, "gradcheck found {} mismatches:", self.mismatches.len())?;
+        for m in &self.mismatches {
+            writeln!(
+                f,
+                "  input {} at {:?}: analytic {}, numeric {}",
+                m.input, m.index, m.analytic, m.numeric
+            )?;
+        }
+        Ok(())
+    }
+}
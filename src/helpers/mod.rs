@@ -1 +1,2 @@
+pub mod gradcheck;
 pub mod visualization;
@@ -1,15 +1,15 @@
-use crate::autograd::Autograd;
+use crate::autograd::{Autograd, Element};
 use std::collections::HashMap;
 use std::io::Write;
 
-pub struct Visualizer {
+pub struct Visualizer<T: Element = f64> {
     pub vertical: bool,
     pub show_values: bool,
     pub precision: usize,
-    pub output_nodes: Vec<(Autograd, String)>,
+    pub output_nodes: Vec<(Autograd<T>, String)>,
 }
 
-impl Default for Visualizer {
+impl<T: Element> Default for Visualizer<T> {
     fn default() -> Self {
         Self {
             vertical: false,
@@ -20,7 +20,7 @@ impl Default for Visualizer {
     }
 }
 
-impl Visualizer {
+impl<T: Element> Visualizer<T> {
     pub fn new() -> Self {
         Self::default()
     }
@@ -40,17 +40,17 @@ impl Visualizer {
         self
     }
 
-    pub fn add_output(mut self, node: Autograd, name: String) -> Self {
+    pub fn add_output(mut self, node: Autograd<T>, name: String) -> Self {
         self.output_nodes.push((node, name));
         self
     }
 
-    pub fn save(&self, root: &Autograd, path: &str) -> std::io::Result<()> {
+    pub fn save(&self, root: &Autograd<T>, path: &str) -> std::io::Result<()> {
         let mut file = std::fs::File::create(path)?;
         self.draw(root, &mut file)
     }
 
-    pub fn draw(&self, root: &Autograd, writer: &mut impl Write) -> std::io::Result<()> {
+    pub fn draw(&self, root: &Autograd<T>, writer: &mut impl Write) -> std::io::Result<()> {
         let nodes = root.get_topo();
 
         writeln!(
@@ -86,8 +86,8 @@ impl Visualizer {
                     format!(
                         "{}\\ndata: {:.prec$}, grad: {:.prec$}",
                         display_name,
-                        value[[0, 0]],
-                        grad[[0, 0]],
+                        value.sum(),
+                        grad.sum(),
                         prec = self.precision
                     )
                 } else {
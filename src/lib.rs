@@ -1,4 +1,6 @@
 pub mod autograd;
+pub mod error;
+pub mod functional;
 pub mod helpers;
 pub mod nn;
 
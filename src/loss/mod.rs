@@ -4,7 +4,7 @@ pub mod softmax_cross_entropy;
 pub use mse::MSE;
 pub use softmax_cross_entropy::SoftmaxCrossEntropyLoss;
 
-use crate::autograd::Autograd;
+use crate::autograd::{Autograd, Element};
 
 pub enum Reduction {
     Mean,
@@ -12,6 +12,6 @@ pub enum Reduction {
     None,
 }
 
-pub trait Loss {
-    fn forward(&self, pred: &Vec<Autograd>, target_index: usize) -> Autograd;
+pub trait Loss<T: Element = f64> {
+    fn forward(&self, pred: &[Autograd<T>], target_index: usize) -> Autograd<T>;
 }
@@ -1,7 +1,12 @@
 use ndarray::Array2;
 
-use crate::{autograd::Autograd, loss::Loss};
+use crate::{
+    autograd::{Autograd, Element},
+    loss::Loss,
+};
 
+/// Mean squared error between `pred`, one output per class, and the one-hot
+/// encoding of the target class.
 pub struct MSE {}
 
 impl MSE {
@@ -10,17 +15,18 @@ impl MSE {
     }
 }
 
-impl Loss for MSE {
-    fn forward(&self, pred: &Vec<Autograd>, target_index: usize) -> Autograd {
-        let mut total_loss = Autograd::new(Array2::zeros((1, 1)));
+impl Default for MSE {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        for (i, p) in pred.iter().enumerate() {
-            let target_val = if i == target_index { 1.0 } else { 0.0 };
-            let target = Autograd::new(Array2::from_elem((1, 1), target_val));
-            let diff = p.sub(&target).pow(2.0);
-            total_loss = total_loss.add(&diff);
-        }
+impl<T: Element> Loss<T> for MSE {
+    fn forward(&self, pred: &[Autograd<T>], target_index: usize) -> Autograd<T> {
+        let mut target = Array2::zeros((1, pred.len()));
+        target[[0, target_index]] = T::one();
 
-        total_loss.div(&Autograd::new(Array2::from_elem((1, 1), pred.len() as f64)))
+        let diff = Autograd::concat(pred, 1) - Autograd::constant(target);
+        diff.pow(T::from_f64(2.0).unwrap()).mean_axis(1, true)
     }
 }
@@ -1,6 +1,9 @@
-use crate::autograd::Autograd;
+use crate::autograd::{Autograd, Element};
 use crate::loss::Loss;
 
+/// Cross-entropy of the softmax of `pred`, which holds one logit per class,
+/// e.g. from `MLP::logits`. Computed as `-log_softmax(pred)[target]`, so large
+/// logits cannot overflow.
 pub struct SoftmaxCrossEntropyLoss {}
 
 impl SoftmaxCrossEntropyLoss {
@@ -9,10 +12,16 @@ impl SoftmaxCrossEntropyLoss {
     }
 }
 
-impl Loss for SoftmaxCrossEntropyLoss {
-    fn forward(&self, pred: &Vec<Autograd>, target_index: usize) -> Autograd {
-        let log_prob = pred[target_index].log();
+impl Default for SoftmaxCrossEntropyLoss {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Element> Loss<T> for SoftmaxCrossEntropyLoss {
+    fn forward(&self, pred: &[Autograd<T>], target_index: usize) -> Autograd<T> {
+        let log_probs = Autograd::concat(pred, 1).log_softmax(1);
 
-        log_prob.neg()
+        -log_probs.slice_axis(1, target_index, target_index + 1)
     }
 }
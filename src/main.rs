@@ -1,7 +1,9 @@
 use ndarray::Array2;
-use rust_autograd::autograd::Autograd;
+use rust_autograd::autograd::{Autograd, no_grad, trace};
+#[allow(unused_imports)]
 use rust_autograd::loss::{Loss, MSE, SoftmaxCrossEntropyLoss};
 use rust_autograd::nn::MLP;
+#[allow(unused_imports)]
 use rust_autograd::optimizer::{AdamW, Optimizer, SGD};
 
 fn main() {
@@ -14,7 +16,7 @@ fn main() {
         vec![1.0, 0.0],
         vec![1.0, 1.0],
     ];
-    let targets = vec![0.0, 1.0, 1.0, 0.0];
+    let targets = [0.0, 1.0, 1.0, 0.0];
 
     let epochs = 1000;
     let learning_rate = 0.1;
@@ -29,38 +31,48 @@ fn main() {
 
     let parameters = mlp.parameters();
 
-    for epoch in 1..=epochs {
-        let mut total_loss = Autograd::new(Array2::zeros((1, 1)));
+    // The graph is the same every epoch, so it is traced once and re-run
+    let mut graph = trace(
+        |_: &[Autograd]| {
+            let mut total_loss = Autograd::constant(Array2::zeros((1, 1)));
 
-        for (x_data, &y_target) in inputs.iter().zip(targets.iter()) {
-            let x: Vec<Autograd> = x_data
-                .iter()
-                .map(|&v| Autograd::new(Array2::from_elem((1, 1), v)))
-                .collect();
+            for (x_data, &y_target) in inputs.iter().zip(targets.iter()) {
+                let x: Vec<Autograd> = x_data
+                    .iter()
+                    .map(|&v| Autograd::constant(Array2::from_elem((1, 1), v)))
+                    .collect();
 
-            let outputs = mlp.call(&x);
-            let loss = loss_fn.forward(&outputs, y_target as usize);
+                // The loss applies the softmax itself
+                let outputs = mlp.logits(&x);
+                let loss = loss_fn.forward(&outputs, y_target as usize);
 
-            total_loss = total_loss.add(&loss);
-        }
+                total_loss = total_loss + loss;
+            }
+            total_loss
+        },
+        &[],
+    );
+
+    for epoch in 1..=epochs {
+        graph.forward(&[]).unwrap();
 
         optimizer.zero_grad(&parameters);
-        total_loss.set_grad(Array2::from_elem((1, 1), 1.0));
-        total_loss.backward();
+        graph.backward().unwrap();
 
         optimizer.step(&parameters);
 
         if epoch % 50 == 0 || epoch == 1 {
-            let loss_val = total_loss.value()[[0, 0]];
+            let loss_val = graph.output()[[0, 0]];
             println!("Epoch {:3} | Loss: {:.6}", epoch, loss_val);
         }
     }
 
     println!("\nTesting predictions:");
+    let _guard = no_grad();
     for x_data in &inputs {
         let x: Vec<Autograd> = x_data
             .iter()
-            .map(|&v| Autograd::new(Array2::from_elem((1, 1), v)))
+            .map(|&v| Autograd::constant(Array2::from_elem((1, 1), v)))
             .collect();
         let outputs = mlp.call(&x);
         println!(
@@ -2,63 +2,80 @@ use ndarray::Array2;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 
-use crate::autograd::Autograd;
+use crate::autograd::{Autograd, Element};
 
 #[derive(Debug, Clone, Copy)]
 pub enum Activation {
     ReLU,
     Tanh,
+    Sigmoid,
+    Gelu,
+    SiLU,
+    /// Leaky ReLU with the given slope for negative inputs.
+    LeakyReLU(f64),
+    /// ELU with the given alpha.
+    Elu(f64),
+    Softplus,
     Softmax,
     None,
 }
 
 #[derive(Debug, Clone)]
-pub struct Neuron {
-    weights: Vec<Autograd>,
-    bias: Autograd,
+pub struct Neuron<T: Element = f64> {
+    weights: Vec<Autograd<T>>,
+    bias: Autograd<T>,
 }
 
-impl Neuron {
+impl<T: Element> Neuron<T> {
     pub fn new(nin: usize, seed: u64) -> Self {
         let mut rng = StdRng::seed_from_u64(seed);
 
         let scale = (2.0 / nin as f64).sqrt();
         let weights = (0..nin)
-            .map(|_| Autograd::new(Array2::from_elem((1, 1), rng.gen_range(-scale..scale))))
+            .map(|_| {
+                let w = T::from_f64(rng.gen_range(-scale..scale)).unwrap();
+                Autograd::new(Array2::from_elem((1, 1), w))
+            })
             .collect();
-        let bias = Autograd::new(Array2::from_elem((1, 1), 0.0));
+        let bias = Autograd::new(Array2::from_elem((1, 1), T::zero()));
 
         Self { weights, bias }
     }
 
-    pub fn call(&self, x: &[Autograd], activation: Activation) -> Autograd {
+    pub fn call(&self, x: &[Autograd<T>], activation: Activation) -> Autograd<T> {
         let mut sum = self.bias.clone();
         for (w, xi) in self.weights.iter().zip(x.iter()) {
             // sum = sum + w * xi
-            sum = sum.add(&w.mul(xi));
+            sum = sum + w * xi;
         }
 
         match activation {
             Activation::ReLU => sum.relu(),
             Activation::Tanh => sum.tanh(),
+            Activation::Sigmoid => sum.sigmoid(),
+            Activation::Gelu => sum.gelu(),
+            Activation::SiLU => sum.silu(),
+            Activation::LeakyReLU(slope) => sum.leaky_relu(T::from_f64(slope).unwrap()),
+            Activation::Elu(alpha) => sum.elu(T::from_f64(alpha).unwrap()),
+            Activation::Softplus => sum.softplus(),
             Activation::Softmax => sum,
             Activation::None => sum,
         }
     }
 
-    pub fn parameters(&self) -> Vec<Autograd> {
+    pub fn parameters(&self) -> Vec<Autograd<T>> {
         let mut params = self.weights.clone();
         params.push(self.bias.clone());
         params
     }
 }
 
-pub struct Layer {
-    neurons: Vec<Neuron>,
+pub struct Layer<T: Element = f64> {
+    neurons: Vec<Neuron<T>>,
     activation: Activation,
 }
 
-impl Layer {
+impl<T: Element> Layer<T> {
     pub fn new(nin: usize, nout: usize, activation: Activation, seed: u64) -> Self {
         let neurons = (0..nout)
             .map(|i| Neuron::new(nin, seed + i as u64))
@@ -69,12 +86,8 @@ impl Layer {
         }
     }
 
-    pub fn call(&self, x: &[Autograd]) -> Vec<Autograd> {
-        let outputs = self
-            .neurons
-            .iter()
-            .map(|n| n.call(x, self.activation))
-            .collect::<Vec<Autograd>>();
+    pub fn call(&self, x: &[Autograd<T>]) -> Vec<Autograd<T>> {
+        let outputs = self.logits(x);
 
         if let Activation::Softmax = self.activation {
             return self.softmax_layer(&outputs);
@@ -82,28 +95,34 @@ impl Layer {
         outputs
     }
 
-    pub fn parameters(&self) -> Vec<Autograd> {
-        self.neurons.iter().flat_map(|n| n.parameters()).collect()
+    /// Like `call`, but a `Softmax` layer returns its inputs to the softmax.
+    /// This is what losses that apply the softmax themselves expect.
+    pub fn logits(&self, x: &[Autograd<T>]) -> Vec<Autograd<T>> {
+        self.neurons
+            .iter()
+            .map(|n| n.call(x, self.activation))
+            .collect()
     }
 
-    fn softmax_layer(&self, logits: &Vec<Autograd>) -> Vec<Autograd> {
-        let exps: Vec<Autograd> = logits.iter().map(|x| x.exp()).collect();
-
-        let mut sum_exps = exps[0].clone();
+    pub fn parameters(&self) -> Vec<Autograd<T>> {
+        self.neurons.iter().flat_map(|n| n.parameters()).collect()
+    }
 
-        for i in 1..exps.len() {
-            sum_exps = sum_exps.add(&exps[i]);
-        }
+    fn softmax_layer(&self, logits: &[Autograd<T>]) -> Vec<Autograd<T>> {
+        // Softmax over all neurons at once, so that it is shifted by their max
+        let probs = Autograd::concat(logits, 1).softmax(1);
 
-        exps.into_iter().map(|x| x.div(&sum_exps)).collect()
+        (0..logits.len())
+            .map(|i| probs.slice_axis(1, i, i + 1))
+            .collect()
     }
 }
 
-pub struct MLP {
-    layers: Vec<Layer>,
+pub struct MLP<T: Element = f64> {
+    layers: Vec<Layer<T>>,
 }
 
-impl MLP {
+impl<T: Element> MLP<T> {
     pub fn new(nin: usize, nouts: &[usize], seed: u64) -> Self {
         let mut sizes = vec![nin];
         sizes.extend_from_slice(nouts);
@@ -122,7 +141,7 @@ impl MLP {
         Self { layers }
     }
 
-    pub fn call(&self, x: &[Autograd]) -> Vec<Autograd> {
+    pub fn call(&self, x: &[Autograd<T>]) -> Vec<Autograd<T>> {
         let mut current = x.to_vec();
         for layer in &self.layers {
             current = layer.call(&current);
@@ -130,7 +149,18 @@ impl MLP {
         current
     }
 
-    pub fn parameters(&self) -> Vec<Autograd> {
+    /// Outputs of the last layer before its softmax, for
+    /// `SoftmaxCrossEntropyLoss`.
+    pub fn logits(&self, x: &[Autograd<T>]) -> Vec<Autograd<T>> {
+        let (last, hidden) = self.layers.split_last().expect("MLP has no layers");
+        let mut current = x.to_vec();
+        for layer in hidden {
+            current = layer.call(&current);
+        }
+        last.logits(&current)
+    }
+
+    pub fn parameters(&self) -> Vec<Autograd<T>> {
         self.layers.iter().flat_map(|l| l.parameters()).collect()
     }
 
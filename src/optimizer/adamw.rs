@@ -1,20 +1,20 @@
-use crate::autograd::Autograd;
+use crate::autograd::{Autograd, Element};
 use crate::optimizer::Optimizer;
-use ndarray::Array2;
+use ndarray::ArrayD;
 use std::collections::HashMap;
 
-pub struct AdamW {
+pub struct AdamW<T: Element = f64> {
     pub learning_rate: f64,
     pub beta1: f64,
     pub beta2: f64,
     pub epsilon: f64,
     pub weight_decay: f64,
     pub t: u32,
-    m: HashMap<*const (), Array2<f64>>,
-    v: HashMap<*const (), Array2<f64>>,
+    m: HashMap<*const (), ArrayD<T>>,
+    v: HashMap<*const (), ArrayD<T>>,
 }
 
-impl AdamW {
+impl<T: Element> AdamW<T> {
     pub fn new(learning_rate: f64) -> Self {
         Self {
             learning_rate,
@@ -48,12 +48,25 @@ impl AdamW {
     }
 }
 
-impl Optimizer for AdamW {
-    fn step(&mut self, parameters: &[Autograd]) {
+impl<T: Element> Optimizer<T> for AdamW<T> {
+    fn step(&mut self, parameters: &[Autograd<T>]) {
         self.t += 1;
         let t = self.t as f64;
 
+        let one = T::one();
+        let lr = T::from_f64(self.learning_rate).unwrap();
+        let beta1 = T::from_f64(self.beta1).unwrap();
+        let beta2 = T::from_f64(self.beta2).unwrap();
+        let epsilon = T::from_f64(self.epsilon).unwrap();
+        let weight_decay = T::from_f64(self.weight_decay).unwrap();
+        let bias_correction1 = T::from_f64(1.0 - self.beta1.powf(t)).unwrap();
+        let bias_correction2 = T::from_f64(1.0 - self.beta2.powf(t)).unwrap();
+
         for p in parameters {
+            // Frozen parameters are left untouched
+            if !p.requires_grad() {
+                continue;
+            }
             let ptr = p.as_ptr();
             let grad = p.grad();
             let value = p.value();
@@ -62,31 +75,30 @@ impl Optimizer for AdamW {
             let m = self
                 .m
                 .entry(ptr)
-                .or_insert_with(|| Array2::zeros(grad.raw_dim()));
+                .or_insert_with(|| ArrayD::zeros(grad.raw_dim()));
 
             // b_1 * m_{t-1} + (1 - b_1) * g_t
-            *m = self.beta1 * &*m + (1.0 - self.beta1) * &grad;
+            *m = &*m * beta1 + &grad * (one - beta1);
 
             // Get or initialize second moment (Variance)
             let v = self
                 .v
                 .entry(ptr)
-                .or_insert_with(|| Array2::zeros(grad.raw_dim()));
+                .or_insert_with(|| ArrayD::zeros(grad.raw_dim()));
 
             // b_2 * v_{t-1} + (1 - b_2) * g_t^2
-            *v = self.beta2 * &*v + (1.0 - self.beta2) * &grad * &grad;
+            *v = &*v * beta2 + &grad * &grad * (one - beta2);
 
             // Bias correction
             // m_hat = m / (1 - b_1^t)
-            let m_hat = m.clone() / (1.0 - self.beta1.powf(t));
+            let m_hat = m.clone() / bias_correction1;
             // v_hat = v / (1 - b_2^t)
-            let v_hat = v.clone() / (1.0 - self.beta2.powf(t));
+            let v_hat = v.clone() / bias_correction2;
 
             // AdamW update: decouple weight decay
             // w = w - lr * (m_hat / (sqrt(v_hat) + eps) + wd * w)
-            let update =
-                m_hat / (v_hat.mapv(|x| x.sqrt()) + self.epsilon) + &value * self.weight_decay;
-            let new_val = value - update * self.learning_rate;
+            let update = m_hat / (v_hat.mapv(|x| x.sqrt()) + epsilon) + &value * weight_decay;
+            let new_val = value - update * lr;
 
             p.set_value(new_val);
         }
@@ -1,4 +1,4 @@
-use crate::autograd::Autograd;
+use crate::autograd::{Autograd, Element};
 
 pub mod adamw;
 pub mod sgd;
@@ -6,9 +6,9 @@ pub mod sgd;
 pub use adamw::AdamW;
 pub use sgd::SGD;
 
-pub trait Optimizer {
-    fn step(&mut self, parameters: &[Autograd]);
-    fn zero_grad(&self, parameters: &[Autograd]) {
+pub trait Optimizer<T: Element = f64> {
+    fn step(&mut self, parameters: &[Autograd<T>]);
+    fn zero_grad(&self, parameters: &[Autograd<T>]) {
         for p in parameters {
             p.zero_grad();
         }
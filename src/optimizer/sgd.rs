@@ -1,4 +1,4 @@
-use crate::autograd::Autograd;
+use crate::autograd::{Autograd, Element};
 use crate::optimizer::Optimizer;
 
 pub struct SGD {
@@ -11,13 +11,19 @@ impl SGD {
     }
 }
 
-impl Optimizer for SGD {
-    fn step(&mut self, parameters: &[Autograd]) {
+impl<T: Element> Optimizer<T> for SGD {
+    fn step(&mut self, parameters: &[Autograd<T>]) {
+        let learning_rate = T::from_f64(self.learning_rate).unwrap();
+
         for p in parameters {
+            // Frozen parameters are left untouched
+            if !p.requires_grad() {
+                continue;
+            }
             // w = w - lr * g
             let value = p.value();
             let grad = p.grad();
-            let new_val = value - self.learning_rate * grad;
+            let new_val = value - grad * learning_rate;
 
             p.set_value(new_val);
         }
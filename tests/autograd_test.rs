@@ -1,17 +1,19 @@
-use ndarray::array;
-use rust_autograd::autograd::Autograd;
+use ndarray::{Array2, Array3, array};
+use rust_autograd::autograd::{Autograd, BackwardOptions, is_grad_enabled, no_grad};
+use rust_autograd::error::Error;
+use std::cell::Cell;
+use std::rc::Rc;
 
 #[test]
 fn test_add() {
     let a = Autograd::new(array![[1.0, 2.0], [3.0, 4.0]]);
     let b = Autograd::new(array![[5.0, 6.0], [7.0, 8.0]]);
     let c = a.add(&b);
-    assert_eq!(c.value(), array![[6.0, 8.0], [10.0, 12.0]]);
+    assert_eq!(c.value(), array![[6.0, 8.0], [10.0, 12.0]].into_dyn());
 
-    c.set_grad(array![[1.0, 1.0], [1.0, 1.0]]);
-    c.backward();
-    assert_eq!(a.grad(), array![[1.0, 1.0], [1.0, 1.0]]);
-    assert_eq!(b.grad(), array![[1.0, 1.0], [1.0, 1.0]]);
+    c.backward_with(&array![[1.0, 1.0], [1.0, 1.0]]).unwrap();
+    assert_eq!(a.grad(), array![[1.0, 1.0], [1.0, 1.0]].into_dyn());
+    assert_eq!(b.grad(), array![[1.0, 1.0], [1.0, 1.0]].into_dyn());
 }
 
 #[test]
@@ -19,12 +21,35 @@ fn test_mul() {
     let a = Autograd::new(array![[1.0, 2.0], [3.0, 4.0]]);
     let b = Autograd::new(array![[5.0, 6.0], [7.0, 8.0]]);
     let c = a.mul(&b);
-    assert_eq!(c.value(), array![[19.0, 22.0], [43.0, 50.0]]);
+    assert_eq!(c.value(), array![[5.0, 12.0], [21.0, 32.0]].into_dyn());
 
-    c.set_grad(array![[1.0, 0.0], [0.0, 1.0]]);
-    c.backward();
-    assert_eq!(a.grad(), array![[5.0, 7.0], [6.0, 8.0]]);
-    assert_eq!(b.grad(), array![[1.0, 3.0], [2.0, 4.0]]);
+    c.backward_with(&array![[1.0, 0.0], [0.0, 1.0]]).unwrap();
+    assert_eq!(a.grad(), array![[5.0, 0.0], [0.0, 8.0]].into_dyn());
+    assert_eq!(b.grad(), array![[1.0, 0.0], [0.0, 4.0]].into_dyn());
+}
+
+#[test]
+fn test_mul_broadcast() {
+    let a = Autograd::new(array![[1.0, 2.0], [3.0, 4.0]]);
+    let mask = Autograd::new(array![[0.0, 1.0]]);
+    let c = a.mul(&mask);
+    assert_eq!(c.value(), array![[0.0, 2.0], [0.0, 4.0]].into_dyn());
+
+    c.backward_with(&Array2::ones((2, 2))).unwrap();
+    assert_eq!(a.grad(), array![[0.0, 1.0], [0.0, 1.0]].into_dyn());
+    assert_eq!(mask.grad(), array![[4.0, 6.0]].into_dyn());
+}
+
+#[test]
+fn test_matmul() {
+    let a = Autograd::new(array![[1.0, 2.0], [3.0, 4.0]]);
+    let b = Autograd::new(array![[5.0, 6.0], [7.0, 8.0]]);
+    let c = a.matmul(&b);
+    assert_eq!(c.value(), array![[19.0, 22.0], [43.0, 50.0]].into_dyn());
+
+    c.backward_with(&array![[1.0, 0.0], [0.0, 1.0]]).unwrap();
+    assert_eq!(a.grad(), array![[5.0, 7.0], [6.0, 8.0]].into_dyn());
+    assert_eq!(b.grad(), array![[1.0, 3.0], [2.0, 4.0]].into_dyn());
 }
 
 #[test]
@@ -32,12 +57,11 @@ fn test_div() {
     let a = Autograd::new(array![[10.0, 20.0]]);
     let b = Autograd::new(array![[2.0, 4.0]]);
     let c = a.div(&b);
-    assert_eq!(c.value(), array![[5.0, 5.0]]);
+    assert_eq!(c.value(), array![[5.0, 5.0]].into_dyn());
 
-    c.set_grad(array![[1.0, 1.0]]);
-    c.backward();
-    assert_eq!(a.grad(), array![[0.5, 0.25]]);
-    assert_eq!(b.grad(), array![[-2.5, -1.25]]);
+    c.backward_with(&array![[1.0, 1.0]]).unwrap();
+    assert_eq!(a.grad(), array![[0.5, 0.25]].into_dyn());
+    assert_eq!(b.grad(), array![[-2.5, -1.25]].into_dyn());
 }
 
 #[test]
@@ -45,36 +69,33 @@ fn test_sub() {
     let a = Autograd::new(array![[10.0, 5.0]]);
     let b = Autograd::new(array![[3.0, 2.0]]);
     let c = a.sub(&b);
-    assert_eq!(c.value(), array![[7.0, 3.0]]);
+    assert_eq!(c.value(), array![[7.0, 3.0]].into_dyn());
 
-    c.set_grad(array![[1.0, 1.0]]);
-    c.backward();
-    assert_eq!(a.grad(), array![[1.0, 1.0]]);
-    assert_eq!(b.grad(), array![[-1.0, -1.0]]);
+    c.backward_with(&array![[1.0, 1.0]]).unwrap();
+    assert_eq!(a.grad(), array![[1.0, 1.0]].into_dyn());
+    assert_eq!(b.grad(), array![[-1.0, -1.0]].into_dyn());
 }
 
 #[test]
 fn test_pow() {
     let a = Autograd::new(array![[2.0, 3.0]]);
     let b = a.pow(2.0);
-    assert_eq!(b.value(), array![[4.0, 9.0]]);
+    assert_eq!(b.value(), array![[4.0, 9.0]].into_dyn());
 
-    b.set_grad(array![[1.0, 1.0]]);
-    b.backward();
+    b.backward_with(&array![[1.0, 1.0]]).unwrap();
     // d(x^2)/dx = 2*x
-    assert_eq!(a.grad(), array![[4.0, 6.0]]);
+    assert_eq!(a.grad(), array![[4.0, 6.0]].into_dyn());
 }
 
 #[test]
 fn test_log() {
     let a = Autograd::new(array![[10.0, 20.0]]);
     let b = a.log();
-    assert!((b.value()[[0, 0]] - 2.302585092994046).abs() < 1e-10);
+    assert!((b.value()[[0, 0]] - std::f64::consts::LN_10).abs() < 1e-10);
     assert!((b.value()[[0, 1]] - 2.995732273553991).abs() < 1e-10);
 
     // dlog(x)/dx = 1/x
-    b.set_grad(array![[1.0, 1.0]]);
-    b.backward();
+    b.backward_with(&array![[1.0, 1.0]]).unwrap();
     // Use epsilon for float comparison
     assert!((a.grad()[[0, 0]] - 0.1).abs() < 1e-7);
     assert!((a.grad()[[0, 1]] - 0.05).abs() < 1e-7);
@@ -84,22 +105,20 @@ fn test_log() {
 fn test_neg() {
     let a = Autograd::new(array![[1.0, -2.0]]);
     let b = a.neg();
-    assert_eq!(b.value(), array![[-1.0, 2.0]]);
+    assert_eq!(b.value(), array![[-1.0, 2.0]].into_dyn());
 
-    b.set_grad(array![[1.0, 1.0]]);
-    b.backward();
-    assert_eq!(a.grad(), array![[-1.0, -1.0]]);
+    b.backward_with(&array![[1.0, 1.0]]).unwrap();
+    assert_eq!(a.grad(), array![[-1.0, -1.0]].into_dyn());
 }
 
 #[test]
 fn test_exp() {
-    let a = Autograd::new(array![[0.0, 1.0]]);
+    let a: Autograd = Autograd::new(array![[0.0, 1.0]]);
     let b = a.exp();
     assert!((b.value()[[0, 0]] - 1.0).abs() < 1e-10);
     assert!((b.value()[[0, 1]] - std::f64::consts::E).abs() < 1e-10);
 
-    b.set_grad(array![[1.0, 1.0]]);
-    b.backward();
+    b.backward_with(&array![[1.0, 1.0]]).unwrap();
     assert!((a.grad()[[0, 0]] - 1.0).abs() < 1e-10);
     assert!((a.grad()[[0, 1]] - std::f64::consts::E).abs() < 1e-10);
 }
@@ -108,11 +127,10 @@ fn test_exp() {
 fn test_relu() {
     let a = Autograd::new(array![[-1.0, 2.0]]);
     let b = a.relu();
-    assert_eq!(b.value(), array![[0.0, 2.0]]);
+    assert_eq!(b.value(), array![[0.0, 2.0]].into_dyn());
 
-    b.set_grad(array![[1.0, 1.0]]);
-    b.backward();
-    assert_eq!(a.grad(), array![[0.0, 1.0]]);
+    b.backward_with(&array![[1.0, 1.0]]).unwrap();
+    assert_eq!(a.grad(), array![[0.0, 1.0]].into_dyn());
 }
 
 #[test]
@@ -121,7 +139,543 @@ fn test_tanh() {
     let b = a.tanh();
     assert_eq!(b.value()[[0, 0]], 0.0);
 
-    b.set_grad(array![[1.0]]);
-    b.backward();
+    b.backward().unwrap();
     assert_eq!(a.grad()[[0, 0]], 1.0);
 }
+
+#[test]
+fn test_add_broadcast() {
+    let a = Autograd::new(array![[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]);
+    let b = Autograd::new(array![[10.0, 20.0]]);
+    let c = a.add(&b);
+    assert_eq!(
+        c.value(),
+        array![[11.0, 22.0], [13.0, 24.0], [15.0, 26.0]].into_dyn()
+    );
+
+    c.backward_with(&Array2::ones((3, 2))).unwrap();
+    assert_eq!(a.grad(), Array2::ones((3, 2)).into_dyn());
+    // the bias row receives the gradient summed over the batch
+    assert_eq!(b.grad(), array![[3.0, 3.0]].into_dyn());
+}
+
+#[test]
+fn test_sub_broadcast() {
+    let a = Autograd::new(array![[1.0, 2.0], [3.0, 4.0]]);
+    let b = Autograd::new(array![[1.0], [2.0]]);
+    let c = a.sub(&b);
+    assert_eq!(c.value(), array![[0.0, 1.0], [1.0, 2.0]].into_dyn());
+
+    c.backward_with(&Array2::ones((2, 2))).unwrap();
+    assert_eq!(a.grad(), Array2::ones((2, 2)).into_dyn());
+    assert_eq!(b.grad(), array![[-2.0], [-2.0]].into_dyn());
+}
+
+#[test]
+fn test_div_broadcast() {
+    let a = Autograd::new(array![[2.0, 4.0], [6.0, 8.0]]);
+    let b = Autograd::new(array![[2.0]]);
+    let c = a.div(&b);
+    assert_eq!(c.value(), array![[1.0, 2.0], [3.0, 4.0]].into_dyn());
+
+    c.backward_with(&Array2::ones((2, 2))).unwrap();
+    assert_eq!(a.grad(), array![[0.5, 0.5], [0.5, 0.5]].into_dyn());
+    // dy/db = -sum(a) / b^2 = -20 / 4
+    assert_eq!(b.grad(), array![[-5.0]].into_dyn());
+}
+
+#[test]
+fn test_nd_add_broadcast() {
+    // (N, T, D) activations plus a (D,) bias
+    let a = Autograd::new(Array3::<f64>::zeros((2, 3, 4)));
+    let b = Autograd::new(array![1.0, 2.0, 3.0, 4.0]);
+    let c = a.add(&b);
+    assert_eq!(c.value().shape(), &[2, 3, 4]);
+    assert_eq!(c.value()[[1, 2, 3]], 4.0);
+
+    c.backward_with(&Array3::<f64>::ones((2, 3, 4))).unwrap();
+    assert_eq!(a.grad(), Array3::<f64>::ones((2, 3, 4)).into_dyn());
+    assert_eq!(b.grad(), array![6.0, 6.0, 6.0, 6.0].into_dyn());
+}
+
+#[test]
+fn test_batched_matmul_shared_rhs() {
+    // (2, 1, 2) @ (2, 2) -> (2, 1, 2)
+    let a = Autograd::new(array![[[1.0, 2.0]], [[3.0, 4.0]]]);
+    let b = Autograd::new(array![[1.0, 0.0], [0.0, 2.0]]);
+    let c = a.matmul(&b);
+    assert_eq!(c.value(), array![[[1.0, 4.0]], [[3.0, 8.0]]].into_dyn());
+
+    c.backward_with(&Array3::<f64>::ones((2, 1, 2))).unwrap();
+    assert_eq!(a.grad(), array![[[1.0, 2.0]], [[1.0, 2.0]]].into_dyn());
+    assert_eq!(b.grad(), array![[4.0, 4.0], [6.0, 6.0]].into_dyn());
+}
+
+#[test]
+fn test_batched_matmul() {
+    // (2, 1, 2) @ (2, 2, 1) -> (2, 1, 1)
+    let a = Autograd::new(array![[[1.0, 2.0]], [[3.0, 4.0]]]);
+    let b = Autograd::new(array![[[1.0], [1.0]], [[2.0], [0.0]]]);
+    let c = a.matmul(&b);
+    assert_eq!(c.value(), array![[[3.0]], [[6.0]]].into_dyn());
+
+    c.backward_with(&Array3::<f64>::ones((2, 1, 1))).unwrap();
+    assert_eq!(a.grad(), array![[[1.0, 1.0]], [[2.0, 0.0]]].into_dyn());
+    assert_eq!(b.grad(), array![[[1.0], [2.0]], [[3.0], [4.0]]].into_dyn());
+}
+
+#[test]
+fn test_f32_graph() {
+    let a = Autograd::new(array![[1.0f32, 2.0]]);
+    let b = Autograd::new(array![[3.0f32, 4.0]]);
+    let c = a.mul(&b).add(&a);
+    assert_eq!(c.value(), array![[4.0f32, 10.0]].into_dyn());
+
+    c.backward_with(&array![[1.0f32, 1.0]]).unwrap();
+    assert_eq!(a.grad(), array![[4.0f32, 5.0]].into_dyn());
+    assert_eq!(b.grad(), array![[1.0f32, 2.0]].into_dyn());
+}
+
+#[test]
+fn test_cast() {
+    let a = Autograd::new(array![[0.1f64, 2.5]]);
+    a.set_name("w");
+    let b: Autograd<f32> = a.cast();
+    assert_eq!(b.value(), array![[0.1f32, 2.5]].into_dyn());
+    assert_eq!(b.name(), "w");
+
+    let c: Autograd<f64> = b.cast();
+    assert!((c.value()[[0, 0]] - 0.1).abs() < 1e-7);
+}
+
+#[test]
+fn test_sum() {
+    let a = Autograd::new(array![[1.0, 2.0], [3.0, 4.0]]);
+    let b = a.sum();
+    assert_eq!(b.value(), ndarray::arr0(10.0).into_dyn());
+
+    b.backward().unwrap();
+    assert_eq!(a.grad(), Array2::ones((2, 2)).into_dyn());
+}
+
+#[test]
+fn test_sum_axis() {
+    let a = Autograd::new(array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+    let b = a.sum_axis(1, true);
+    assert_eq!(b.value(), array![[6.0], [15.0]].into_dyn());
+
+    b.backward_with(&array![[1.0], [2.0]]).unwrap();
+    assert_eq!(
+        a.grad(),
+        array![[1.0, 1.0, 1.0], [2.0, 2.0, 2.0]].into_dyn()
+    );
+}
+
+#[test]
+fn test_mean_axis() {
+    let a = Autograd::new(array![[1.0, 2.0], [3.0, 6.0]]);
+    let b = a.mean_axis(0, false);
+    assert_eq!(b.value(), array![2.0, 4.0].into_dyn());
+
+    b.backward_with(&array![1.0, 1.0]).unwrap();
+    assert_eq!(a.grad(), array![[0.5, 0.5], [0.5, 0.5]].into_dyn());
+
+    let c = Autograd::new(array![[1.0, 2.0], [3.0, 6.0]]);
+    let d = c.mean();
+    assert_eq!(d.value(), ndarray::arr0(3.0).into_dyn());
+}
+
+#[test]
+fn test_max() {
+    let a = Autograd::new(array![[1.0, 7.0], [7.0, 2.0]]);
+    let b = a.max();
+    assert_eq!(b.value(), ndarray::arr0(7.0).into_dyn());
+
+    b.backward().unwrap();
+    // ties go to the first maximal element only
+    assert_eq!(a.grad(), array![[0.0, 1.0], [0.0, 0.0]].into_dyn());
+}
+
+#[test]
+fn test_max_min_axis() {
+    let a = Autograd::new(array![[1.0, 5.0, 3.0], [4.0, 2.0, 6.0]]);
+    let b = a.max_axis(1, false);
+    assert_eq!(b.value(), array![5.0, 6.0].into_dyn());
+
+    b.backward_with(&array![1.0, 2.0]).unwrap();
+    assert_eq!(
+        a.grad(),
+        array![[0.0, 1.0, 0.0], [0.0, 0.0, 2.0]].into_dyn()
+    );
+
+    let c = Autograd::new(array![[1.0, 5.0, 3.0], [4.0, 2.0, 6.0]]);
+    let d = c.min_axis(0, true);
+    assert_eq!(d.value(), array![[1.0, 2.0, 3.0]].into_dyn());
+
+    d.backward_with(&array![[1.0, 1.0, 1.0]]).unwrap();
+    assert_eq!(
+        c.grad(),
+        array![[1.0, 0.0, 1.0], [0.0, 1.0, 0.0]].into_dyn()
+    );
+}
+
+#[test]
+fn test_reshape() {
+    let a = Autograd::new(array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+    let b = a.reshape(&[3, 2]);
+    assert_eq!(
+        b.value(),
+        array![[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]].into_dyn()
+    );
+
+    b.backward_with(&array![[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]])
+        .unwrap();
+    assert_eq!(
+        a.grad(),
+        array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]].into_dyn()
+    );
+}
+
+#[test]
+fn test_transpose_permute() {
+    let a = Autograd::new(array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+    let b = a.transpose();
+    assert_eq!(
+        b.value(),
+        array![[1.0, 4.0], [2.0, 5.0], [3.0, 6.0]].into_dyn()
+    );
+
+    b.backward_with(&array![[1.0, 0.0], [2.0, 0.0], [3.0, 0.0]])
+        .unwrap();
+    assert_eq!(
+        a.grad(),
+        array![[1.0, 2.0, 3.0], [0.0, 0.0, 0.0]].into_dyn()
+    );
+
+    let c = Autograd::new(Array3::<f64>::zeros((2, 3, 4)));
+    let d = c.permute(&[2, 0, 1]);
+    assert_eq!(d.value().shape(), &[4, 2, 3]);
+
+    d.backward_with(&Array3::<f64>::ones((4, 2, 3))).unwrap();
+    assert_eq!(c.grad(), Array3::<f64>::ones((2, 3, 4)).into_dyn());
+}
+
+#[test]
+fn test_concat() {
+    let a = Autograd::new(array![[1.0], [2.0]]);
+    let b = Autograd::new(array![[3.0, 4.0], [5.0, 6.0]]);
+    let c = Autograd::concat(&[a.clone(), b.clone()], 1);
+    assert_eq!(
+        c.value(),
+        array![[1.0, 3.0, 4.0], [2.0, 5.0, 6.0]].into_dyn()
+    );
+
+    c.backward_with(&array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]])
+        .unwrap();
+    assert_eq!(a.grad(), array![[1.0], [4.0]].into_dyn());
+    assert_eq!(b.grad(), array![[2.0, 3.0], [5.0, 6.0]].into_dyn());
+}
+
+#[test]
+fn test_stack() {
+    // turn per-neuron 1x1 outputs into a single (2, 1, 1) tensor
+    let a = Autograd::new(array![[1.0]]);
+    let b = Autograd::new(array![[2.0]]);
+    let c = Autograd::stack(&[a.clone(), b.clone()], 0);
+    assert_eq!(c.value(), array![[[1.0]], [[2.0]]].into_dyn());
+
+    c.backward_with(&array![[[3.0]], [[4.0]]]).unwrap();
+    assert_eq!(a.grad(), array![[3.0]].into_dyn());
+    assert_eq!(b.grad(), array![[4.0]].into_dyn());
+}
+
+#[test]
+fn test_slice_axis() {
+    let a = Autograd::new(array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+    let b = a.slice_axis(1, 1, 3);
+    assert_eq!(b.value(), array![[2.0, 3.0], [5.0, 6.0]].into_dyn());
+
+    b.backward_with(&Array2::ones((2, 2))).unwrap();
+    assert_eq!(
+        a.grad(),
+        array![[0.0, 1.0, 1.0], [0.0, 1.0, 1.0]].into_dyn()
+    );
+}
+
+#[test]
+fn test_backward_twice_accumulates() {
+    let a = Autograd::new(array![[2.0]]);
+    let b = a.mul(&a).exp().log();
+
+    b.backward_with_options(BackwardOptions::new().retain_graph(true))
+        .unwrap();
+    assert_eq!(a.grad(), array![[4.0]].into_dyn());
+
+    // Intermediate grads from the first pass must not be propagated again
+    b.backward().unwrap();
+    assert_eq!(a.grad(), array![[8.0]].into_dyn());
+}
+
+#[test]
+fn test_backward_releases_graph() {
+    let a = Autograd::new(array![[1.0, 2.0]]);
+    let c = a.mul(&a);
+    let d = c.sum();
+
+    d.backward().unwrap();
+    assert_eq!(a.grad(), array![[2.0, 4.0]].into_dyn());
+    assert!(d.children().is_empty());
+    assert!(c.children().is_empty());
+    assert_eq!(c.op(), "Released");
+    // Values and gradients of the released nodes are still readable
+    assert_eq!(c.value(), array![[1.0, 4.0]].into_dyn());
+    assert_eq!(c.grad(), array![[1.0, 1.0]].into_dyn());
+
+    assert_eq!(
+        d.backward(),
+        Err(Error::GraphReleased {
+            name: String::new()
+        })
+    );
+    assert_eq!(a.grad(), array![[2.0, 4.0]].into_dyn());
+}
+
+#[test]
+fn test_backward_frees_released_nodes_during_pass() {
+    // Sets the flag when the node holding it is dropped
+    struct DropFlag(Rc<Cell<bool>>);
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.set(true);
+        }
+    }
+
+    let dropped = Rc::new(Cell::new(false));
+    let x = Autograd::new(array![[1.0, 2.0]]);
+    let loss = {
+        let h = x.exp();
+        let flag = DropFlag(dropped.clone());
+        h.register_hook(move |_| {
+            let _ = &flag;
+            None
+        });
+        h.tanh().sum()
+    };
+
+    // By the time the leaf is reached, the interior node has been freed
+    let seen = dropped.clone();
+    x.register_hook(move |_| {
+        assert!(seen.get());
+        None
+    });
+    loss.backward().unwrap();
+    assert!(dropped.get());
+}
+
+#[test]
+fn test_no_grad() {
+    let a = Autograd::new(array![[1.0, 2.0]]);
+    let b = Autograd::new(array![[3.0, 4.0]]);
+
+    {
+        let _guard = no_grad();
+        assert!(!is_grad_enabled());
+        let c = a.mul(&b).exp();
+        assert!(c.children().is_empty());
+        assert_eq!(c.op(), "None");
+        assert_eq!(c.value(), array![[3.0f64.exp(), 8.0f64.exp()]].into_dyn());
+
+        {
+            let _inner = no_grad();
+        }
+        // dropping a nested guard restores the outer (disabled) mode
+        assert!(!is_grad_enabled());
+    }
+
+    assert!(is_grad_enabled());
+    let c = a.mul(&b);
+    assert_eq!(c.children().len(), 2);
+    c.backward_with(&array![[1.0, 1.0]]).unwrap();
+    assert_eq!(a.grad(), array![[3.0, 4.0]].into_dyn());
+}
+
+#[test]
+fn test_backward_seeds_scalar_root() {
+    let a = Autograd::new(array![[1.0, 2.0]]);
+    let loss = a.mul(&a).sum();
+
+    loss.backward().unwrap();
+    assert_eq!(loss.grad(), ndarray::arr0(1.0).into_dyn());
+    assert_eq!(a.grad(), array![[2.0, 4.0]].into_dyn());
+}
+
+#[test]
+fn test_backward_errors() {
+    let a = Autograd::new(array![[1.0, 2.0]]);
+    let b = a.exp();
+
+    assert_eq!(
+        b.backward(),
+        Err(Error::NonScalarRoot { shape: vec![1, 2] })
+    );
+    assert_eq!(
+        b.backward_with(&array![1.0, 1.0]),
+        Err(Error::GradShape {
+            expected: vec![1, 2],
+            got: vec![2],
+        })
+    );
+    assert_eq!(a.grad(), array![[0.0, 0.0]].into_dyn());
+}
+
+#[test]
+fn test_deep_graph() {
+    let x = Autograd::new(array![[1.0]]);
+    let mut total = Autograd::new(array![[0.0]]);
+    for _ in 0..100_000 {
+        total = total + &x;
+    }
+
+    assert_eq!(total.get_topo().len(), 100_002);
+    total
+        .backward_with_options(BackwardOptions::new().retain_graph(true))
+        .unwrap();
+    assert_eq!(x.grad(), array![[100_000.0]].into_dyn());
+    drop(total);
+}
+
+#[test]
+fn test_requires_grad() {
+    let a = Autograd::new(array![[1.0, 2.0]]);
+    let target = Autograd::constant(array![[3.0, 3.0]]);
+    assert!(a.requires_grad());
+    assert!(!target.requires_grad());
+
+    // Results computed only from constants record no graph
+    let shifted = target.add(&target);
+    assert!(!shifted.requires_grad());
+    assert!(shifted.children().is_empty());
+
+    let loss = a.sub(&shifted).pow(2.0).sum();
+    assert!(loss.requires_grad());
+    loss.backward().unwrap();
+    assert_eq!(a.grad(), array![[-10.0, -8.0]].into_dyn());
+    assert_eq!(target.grad(), array![[0.0, 0.0]].into_dyn());
+    assert_eq!(shifted.grad(), array![[0.0, 0.0]].into_dyn());
+
+    assert_eq!(
+        shifted.sum().backward(),
+        Err(Error::NoGrad {
+            name: String::new()
+        })
+    );
+}
+
+#[test]
+fn test_frozen_leaf() {
+    let a = Autograd::new(array![[1.0, 2.0]]);
+    let b = Autograd::new(array![[3.0, 4.0]]);
+    b.set_requires_grad(false);
+
+    a.mul(&b).sum().backward().unwrap();
+    assert_eq!(a.grad(), array![[3.0, 4.0]].into_dyn());
+    assert_eq!(b.grad(), array![[0.0, 0.0]].into_dyn());
+}
+
+#[test]
+fn test_grad_before_backward() {
+    // No gradient has reached `a` yet, so it reads as zeros of its current shape
+    let a = Autograd::new(array![[1.0, 2.0]]);
+    a.set_value(array![1.0, 2.0, 3.0]);
+    assert_eq!(a.grad(), array![0.0, 0.0, 0.0].into_dyn());
+
+    let w = Autograd::new(array![[1.0], [2.0], [3.0]]);
+    w.set_requires_grad(false);
+    a.reshape(&[1, 3]).matmul(&w).sum().backward().unwrap();
+    assert_eq!(a.grad(), array![1.0, 2.0, 3.0].into_dyn());
+    assert_eq!(w.grad(), array![[0.0], [0.0], [0.0]].into_dyn());
+
+    a.zero_grad();
+    assert_eq!(a.grad(), array![0.0, 0.0, 0.0].into_dyn());
+}
+
+#[test]
+fn test_detach() {
+    let a = Autograd::new(array![[1.0, 2.0]]);
+    let target = a.mul(&a).detach();
+    assert!(!target.requires_grad());
+    assert_eq!(target.value(), array![[1.0, 4.0]].into_dyn());
+
+    a.sub(&target).sum().backward().unwrap();
+    assert_eq!(a.grad(), array![[1.0, 1.0]].into_dyn());
+
+    // The detached node shares the value of its source
+    let shared = a.detach();
+    a.set_value(array![[5.0, 6.0]]);
+    assert_eq!(shared.value(), array![[5.0, 6.0]].into_dyn());
+    shared.set_value(array![[7.0, 8.0]]);
+    assert_eq!(a.value(), array![[7.0, 8.0]].into_dyn());
+    assert!(a.requires_grad());
+}
+
+#[test]
+fn test_stop_gradient() {
+    let x = Autograd::new(array![[0.4, 1.7]]);
+    let q = Autograd::constant(array![[0.0, 2.0]]);
+
+    // Straight-through estimator: forward value of q, gradient of x
+    let y = &x + (&q - &x).stop_gradient();
+    assert_eq!(y.value(), array![[0.0, 2.0]].into_dyn());
+    assert_eq!(y.children().len(), 2);
+
+    y.mul(&y).sum().backward().unwrap();
+    assert_eq!(x.grad(), array![[0.0, 4.0]].into_dyn());
+}
+
+#[test]
+fn test_softmax_stable() {
+    let a = Autograd::new(array![[1000.0, 1001.0], [-1000.0, 0.0]]);
+
+    let p = a.softmax(1);
+    let e = 1.0 / (1.0 + 1f64.exp());
+    assert!((p.value()[[0, 0]] - e).abs() < 1e-12);
+    assert!(p.value().iter().all(|x| x.is_finite()));
+
+    let log_p = a.log_softmax(1);
+    assert!((log_p.value()[[1, 0]] + 1000.0).abs() < 1e-9);
+
+    let lse = a.logsumexp_axis(1, false);
+    assert_eq!(lse.value().shape(), &[2]);
+    assert!((lse.value()[0] - (1001.0 + (1.0 + (-1f64).exp()).ln())).abs() < 1e-9);
+    assert_eq!(a.logsumexp().value().shape(), &[] as &[usize]);
+
+    log_p.slice_axis(1, 0, 1).sum().backward().unwrap();
+    assert!(a.grad().iter().all(|x| x.is_finite()));
+    assert!((a.grad()[[0, 0]] - (1.0 - e)).abs() < 1e-12);
+}
+
+#[test]
+fn test_elementwise_subgradients() {
+    let a = Autograd::new(array![[0.0, 2.0, 3.0]]);
+    let b = Autograd::new(array![[1.0, 2.0, 1.0]]);
+
+    let c = a.abs().add(&a.clamp(0.5, 3.0)).add(&a.minimum(&b));
+    assert_eq!(c.value(), array![[0.5, 6.0, 7.0]].into_dyn());
+    c.sum().backward().unwrap();
+    // abs takes 0 at 0, clamp passes inclusive bounds, ties split evenly
+    assert_eq!(a.grad(), array![[1.0, 2.5, 2.0]].into_dyn());
+    assert_eq!(b.grad(), array![[0.0, 0.5, 1.0]].into_dyn());
+}
+
+#[test]
+fn test_where() {
+    let a = Autograd::new(array![[1.0, 2.0], [3.0, 4.0]]);
+    let b = Autograd::new(array![[10.0, 20.0]]);
+    let cond = array![[true, false], [false, true]];
+
+    let c = Autograd::where_(&cond, &a, &b);
+    assert_eq!(c.value(), array![[1.0, 20.0], [10.0, 4.0]].into_dyn());
+    c.backward_with(&array![[1.0, 2.0], [3.0, 4.0]]).unwrap();
+    assert_eq!(a.grad(), array![[1.0, 0.0], [0.0, 4.0]].into_dyn());
+    assert_eq!(b.grad(), array![[3.0, 2.0]].into_dyn());
+}
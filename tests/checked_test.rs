@@ -0,0 +1,159 @@
+use ndarray::{Array3, array};
+use rust_autograd::autograd::Autograd;
+use rust_autograd::error::Error;
+
+#[test]
+fn test_try_binary_ops() {
+    let a = Autograd::new(array![[1.0, 2.0], [3.0, 4.0]]);
+    let b = Autograd::new(array![[10.0, 20.0]]);
+
+    let c = a.try_add(&b).unwrap();
+    assert_eq!(c.value(), array![[11.0, 22.0], [13.0, 24.0]].into_dyn());
+    c.try_mul(&b)
+        .unwrap()
+        .try_div(&a)
+        .unwrap()
+        .try_sub(&a)
+        .unwrap()
+        .sum()
+        .backward()
+        .unwrap();
+    assert_eq!(b.grad().shape(), &[1, 2]);
+}
+
+#[test]
+fn test_try_add_shape_mismatch() {
+    let a = Autograd::new(array![[1.0, 2.0, 3.0]]);
+    let b = Autograd::new(array![[1.0, 2.0]]);
+    a.set_name("x");
+
+    let err = a.try_add(&b).unwrap_err();
+    assert_eq!(
+        err,
+        Error::ShapeMismatch {
+            op: "add",
+            shapes: vec![vec![1, 3], vec![1, 2]],
+            names: vec!["x".to_string(), String::new()],
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "add got incompatible shapes [1, 3] (x) and [1, 2]"
+    );
+}
+
+#[test]
+fn test_try_matmul() {
+    let a = Autograd::new(Array3::<f64>::ones((2, 3, 4)));
+    let w = Autograd::new(ndarray::Array2::<f64>::ones((4, 5)));
+    assert_eq!(a.try_matmul(&w).unwrap().value().shape(), &[2, 3, 5]);
+
+    let bad = Autograd::new(ndarray::Array2::<f64>::ones((3, 5)));
+    assert!(matches!(
+        a.try_matmul(&bad),
+        Err(Error::ShapeMismatch { op: "matmul", .. })
+    ));
+    let batch = Autograd::new(Array3::<f64>::ones((3, 4, 5)));
+    assert!(a.try_matmul(&batch).is_err());
+}
+
+#[test]
+fn test_try_reshape() {
+    let a = Autograd::new(array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+    assert_eq!(a.try_reshape(&[3, 2]).unwrap().value().shape(), &[3, 2]);
+    assert_eq!(
+        a.try_reshape(&[4, 2]).unwrap_err(),
+        Error::ShapeMismatch {
+            op: "reshape",
+            shapes: vec![vec![2, 3], vec![4, 2]],
+            names: vec![String::new()],
+        }
+    );
+}
+
+#[test]
+fn test_try_concat_and_stack() {
+    let a = Autograd::new(array![[1.0, 2.0]]);
+    let b = Autograd::new(array![[3.0, 4.0, 5.0]]);
+    b.set_name("b");
+
+    let joined = Autograd::try_concat(&[a.clone(), b.clone()], 1).unwrap();
+    assert_eq!(joined.value(), array![[1.0, 2.0, 3.0, 4.0, 5.0]].into_dyn());
+    assert_eq!(
+        Autograd::try_concat(&[a.clone(), b.clone()], 0).unwrap_err(),
+        Error::ShapeMismatch {
+            op: "concat",
+            shapes: vec![vec![1, 2], vec![1, 3]],
+            names: vec![String::new(), "b".to_string()],
+        }
+    );
+    assert!(Autograd::try_concat(std::slice::from_ref(&a), 2).is_err());
+    assert!(Autograd::<f64>::try_concat(&[], 0).is_err());
+
+    let stacked = Autograd::try_stack(&[a.clone(), a.clone()], 2).unwrap();
+    assert_eq!(stacked.value().shape(), &[1, 2, 2]);
+    assert!(Autograd::try_stack(&[a.clone(), b], 0).is_err());
+    assert!(Autograd::try_stack(&[a.clone(), a], 3).is_err());
+}
+
+#[test]
+fn test_try_axis_ops() {
+    let a = Autograd::new(array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+
+    assert_eq!(
+        a.try_slice_axis(1, 1, 3).unwrap().value(),
+        array![[2.0, 3.0], [5.0, 6.0]].into_dyn()
+    );
+    assert_eq!(
+        a.try_slice_axis(1, 2, 4).unwrap_err(),
+        Error::ShapeMismatch {
+            op: "slice_axis",
+            shapes: vec![vec![2, 3], vec![1, 2, 4]],
+            names: vec![String::new()],
+        }
+    );
+    assert!(a.try_slice_axis(2, 0, 1).is_err());
+    assert!(a.try_slice_axis(0, 2, 1).is_err());
+
+    assert_eq!(a.try_permute(&[1, 0]).unwrap().value().shape(), &[3, 2]);
+    assert!(a.try_permute(&[0, 0]).is_err());
+    assert!(a.try_permute(&[0, 1, 2]).is_err());
+
+    assert_eq!(
+        a.try_sum_axis(1, false).unwrap().value(),
+        array![6.0, 15.0].into_dyn()
+    );
+    assert_eq!(a.try_mean_axis(0, true).unwrap().value().shape(), &[1, 3]);
+    assert_eq!(a.try_max_axis(1, false).unwrap().value().shape(), &[2]);
+    assert_eq!(a.try_min_axis(0, false).unwrap().value().shape(), &[3]);
+    assert_eq!(
+        a.try_logsumexp_axis(1, true).unwrap().value().shape(),
+        &[2, 1]
+    );
+    for result in [
+        a.try_sum_axis(2, false),
+        a.try_mean_axis(2, false),
+        a.try_max_axis(2, false),
+        a.try_min_axis(2, false),
+        a.try_logsumexp_axis(2, false),
+    ] {
+        assert!(matches!(result, Err(Error::ShapeMismatch { .. })));
+    }
+}
+
+#[test]
+fn test_try_where() {
+    let a = Autograd::new(array![[1.0, 2.0]]);
+    let b = Autograd::new(array![[3.0], [4.0]]);
+
+    let picked = Autograd::try_where_(&array![true, false], &a, &b).unwrap();
+    assert_eq!(picked.value(), array![[1.0, 3.0], [1.0, 4.0]].into_dyn());
+    assert_eq!(
+        Autograd::try_where_(&array![true, false, true], &a, &b).unwrap_err(),
+        Error::ShapeMismatch {
+            op: "where_",
+            shapes: vec![vec![3], vec![1, 2], vec![2, 1]],
+            names: vec![String::new(); 3],
+        }
+    );
+}
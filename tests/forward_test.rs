@@ -0,0 +1,167 @@
+use ndarray::{ArrayD, IxDyn, array};
+use rust_autograd::autograd::{Autograd, BackwardOptions, Function, jacfwd, jvp, no_grad};
+use rust_autograd::error::Error;
+
+// A function of two inputs exercising most ops, with a (2, 3) output
+fn model(x: &[Autograd]) -> Autograd {
+    let (a, w) = (&x[0], &x[1]);
+    let h = a.matmul(w).tanh().add(&a.sum_axis(1, true).sigmoid());
+    let s = h.softmax(1).mul(&h.log_softmax(0));
+    let m = Autograd::where_(&array![[true, false, true]], &s, &h.sin());
+    Autograd::concat(&[m.slice_axis(1, 0, 2), h.max_axis(1, true).sqrt()], 1)
+        .div(&w.abs().mean().add(&Autograd::scalar(1.0)))
+        .add(
+            &a.transpose()
+                .reshape(&[2, 2])
+                .sum_axis(0, false)
+                .pow(2.0)
+                .sum(),
+        )
+}
+
+// Jacobians column by column with reverse mode, for comparison
+fn jacrev(primals: &[ArrayD<f64>]) -> Vec<ArrayD<f64>> {
+    let leaves: Vec<Autograd> = primals.iter().map(|p| Autograd::new(p.clone())).collect();
+    let output = model(&leaves);
+    let out_shape = output.value().shape().to_vec();
+    let mut jacobians: Vec<Vec<f64>> = vec![Vec::new(); primals.len()];
+    for (index, _) in output.value().indexed_iter() {
+        let mut seed = ArrayD::zeros(output.value().raw_dim());
+        seed[&index] = 1.0;
+        leaves.iter().for_each(|l| l.zero_grad());
+        output
+            .backward_with_options(BackwardOptions::new().grad(&seed).retain_graph(true))
+            .unwrap();
+        for (jacobian, leaf) in jacobians.iter_mut().zip(&leaves) {
+            jacobian.extend(leaf.grad().iter());
+        }
+    }
+    jacobians
+        .into_iter()
+        .zip(primals)
+        .map(|(jacobian, primal)| {
+            let mut shape = out_shape.clone();
+            shape.extend_from_slice(primal.shape());
+            ArrayD::from_shape_vec(IxDyn(&shape), jacobian).unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn test_jacfwd_matches_reverse_mode() {
+    let primals = vec![
+        array![[0.3, -0.8], [1.1, 0.4]].into_dyn(),
+        array![[0.5, -1.2, 0.7], [0.9, 0.2, -0.6]].into_dyn(),
+    ];
+
+    let forward = jacfwd(model, &primals).unwrap();
+    let reverse = jacrev(&primals);
+    assert_eq!(forward[0].shape(), &[2, 3, 2, 2]);
+    assert_eq!(forward[1].shape(), &[2, 3, 2, 3]);
+    for (f, r) in forward.iter().zip(&reverse) {
+        assert!((f - r).iter().all(|d| d.abs() < 1e-12), "{}\n{}", f, r);
+    }
+}
+
+#[test]
+fn test_jvp() {
+    let primal = array![[1.0, 2.0, 3.0]].into_dyn();
+    let tangent = array![[1.0, 0.0, -1.0]].into_dyn();
+
+    // Recording is enabled for the trace even inside no_grad
+    let _guard = no_grad();
+    let (value, dy) = jvp(|x| x[0].mul(&x[0]).exp(), &[primal], &[tangent]).unwrap();
+    assert_eq!(
+        value,
+        array![[1f64.exp(), 4f64.exp(), 9f64.exp()]].into_dyn()
+    );
+    assert_eq!(
+        dy,
+        array![[2.0 * 1f64.exp(), 0.0, -6.0 * 9f64.exp()]].into_dyn()
+    );
+
+    let err = jvp(
+        |x| x[0].exp(),
+        &[array![[1.0]].into_dyn()],
+        &[array![1.0].into_dyn()],
+    );
+    assert_eq!(
+        err,
+        Err(Error::GradShape {
+            expected: vec![1, 1],
+            got: vec![1],
+        })
+    );
+}
+
+struct Cube;
+
+impl Function for Cube {
+    fn name(&self) -> &str {
+        "Cube"
+    }
+
+    fn forward(&self, inputs: &[&ArrayD<f64>]) -> ArrayD<f64> {
+        inputs[0].mapv(|x| x * x * x)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[&ArrayD<f64>],
+        _output: &ArrayD<f64>,
+        grad: &ArrayD<f64>,
+    ) -> Vec<ArrayD<f64>> {
+        vec![grad * &inputs[0].mapv(|x| 3.0 * x * x)]
+    }
+}
+
+struct CubeWithJvp;
+
+impl Function for CubeWithJvp {
+    fn name(&self) -> &str {
+        "CubeWithJvp"
+    }
+
+    fn forward(&self, inputs: &[&ArrayD<f64>]) -> ArrayD<f64> {
+        Cube.forward(inputs)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[&ArrayD<f64>],
+        output: &ArrayD<f64>,
+        grad: &ArrayD<f64>,
+    ) -> Vec<ArrayD<f64>> {
+        Cube.backward(inputs, output, grad)
+    }
+
+    fn jvp(
+        &self,
+        inputs: &[&ArrayD<f64>],
+        _output: &ArrayD<f64>,
+        tangents: &[&ArrayD<f64>],
+    ) -> Option<ArrayD<f64>> {
+        Some(tangents[0] * &inputs[0].mapv(|x| 3.0 * x * x))
+    }
+}
+
+#[test]
+fn test_jvp_custom_function() {
+    let primal = array![[2.0]].into_dyn();
+
+    let err = jvp(
+        |x| Autograd::apply(Cube, x),
+        std::slice::from_ref(&primal),
+        &[array![[1.0]].into_dyn()],
+    );
+    assert_eq!(
+        err,
+        Err(Error::NoJvp {
+            op: "Cube".to_string()
+        })
+    );
+
+    let jac = jacfwd(|x| Autograd::apply(CubeWithJvp, x), &[primal]).unwrap();
+    assert_eq!(jac[0].shape(), &[1, 1, 1, 1]);
+    assert_eq!(jac[0].sum(), 12.0);
+}
@@ -0,0 +1,108 @@
+use ndarray::{ArrayD, array};
+use rust_autograd::autograd::{Autograd, Function};
+use rust_autograd::error::Error;
+
+// f(a, b) = a * b^2, elementwise
+struct MulSquare;
+
+impl Function for MulSquare {
+    fn name(&self) -> &str {
+        "MulSquare"
+    }
+
+    fn forward(&self, inputs: &[&ArrayD<f64>]) -> ArrayD<f64> {
+        inputs[0] * &inputs[1].mapv(|x| x * x)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[&ArrayD<f64>],
+        _output: &ArrayD<f64>,
+        grad: &ArrayD<f64>,
+    ) -> Vec<ArrayD<f64>> {
+        let (a, b) = (inputs[0], inputs[1]);
+        vec![grad * &b.mapv(|x| x * x), grad * &(a * b) * 2.0]
+    }
+}
+
+#[test]
+fn test_custom_function() {
+    let a = Autograd::new(array![[1.0, 2.0]]);
+    let b = Autograd::new(array![[3.0, 4.0]]);
+    let c = Autograd::apply(MulSquare, &[a.clone(), b.clone()]);
+    assert_eq!(c.value(), array![[9.0, 32.0]].into_dyn());
+    assert_eq!(c.op(), "MulSquare");
+
+    c.backward_with(&array![[1.0, 1.0]]).unwrap();
+    assert_eq!(a.grad(), array![[9.0, 16.0]].into_dyn());
+    assert_eq!(b.grad(), array![[6.0, 16.0]].into_dyn());
+}
+
+#[test]
+fn test_custom_function_in_graph() {
+    let a = Autograd::new(array![[2.0]]);
+    let b = Autograd::new(array![[1.0]]);
+    // y = -MulSquare(a, b) mixes custom and builtin ops
+    let y = Autograd::apply(MulSquare, &[a.clone(), b.clone()]).neg();
+
+    y.backward().unwrap();
+    assert_eq!(a.grad(), array![[-1.0]].into_dyn());
+    assert_eq!(b.grad(), array![[-4.0]].into_dyn());
+}
+
+// Sums its inputs but returns a single, scalar-shaped gradient
+struct BadGrads;
+
+impl Function for BadGrads {
+    fn name(&self) -> &str {
+        "BadGrads"
+    }
+
+    fn forward(&self, inputs: &[&ArrayD<f64>]) -> ArrayD<f64> {
+        inputs
+            .iter()
+            .fold(ArrayD::zeros(inputs[0].raw_dim()), |acc, x| acc + *x)
+    }
+
+    fn backward(
+        &self,
+        _inputs: &[&ArrayD<f64>],
+        _output: &ArrayD<f64>,
+        grad: &ArrayD<f64>,
+    ) -> Vec<ArrayD<f64>> {
+        vec![ArrayD::from_elem(vec![1], grad.sum())]
+    }
+}
+
+#[test]
+fn test_custom_function_bad_gradients() {
+    let a = Autograd::new(array![1.0, 2.0, 3.0]);
+    let y = Autograd::apply(BadGrads, std::slice::from_ref(&a)).sum();
+    assert_eq!(
+        y.backward(),
+        Err(Error::GradShape {
+            expected: vec![3],
+            got: vec![1],
+        })
+    );
+
+    let y = Autograd::apply(BadGrads, std::slice::from_ref(&a)).sum();
+    assert_eq!(
+        y.backward_create_graph(),
+        Err(Error::GradShape {
+            expected: vec![3],
+            got: vec![1],
+        })
+    );
+
+    let b = Autograd::new(array![4.0, 5.0, 6.0]);
+    let y = Autograd::apply(BadGrads, &[a, b]).sum();
+    assert_eq!(
+        y.backward(),
+        Err(Error::ShapeMismatch {
+            op: "Function::backward",
+            shapes: vec![vec![2], vec![1]],
+            names: vec!["inputs".to_string(), "gradients".to_string()],
+        })
+    );
+}
@@ -0,0 +1,119 @@
+use ndarray::{ArrayD, array};
+use rust_autograd::autograd::{Autograd, no_grad};
+use rust_autograd::error::Error;
+use rust_autograd::functional::{grad, hessian, hvp, jacfwd, jacobian, value_and_grad};
+
+// f(x, y) = sum(x^2 * y)
+fn f(v: &[Autograd]) -> Autograd {
+    v[0].pow(2.0).mul(&v[1]).sum()
+}
+
+fn primals() -> Vec<ArrayD<f64>> {
+    vec![array![1.0, 2.0].into_dyn(), array![3.0, -1.0].into_dyn()]
+}
+
+#[test]
+fn test_grad_and_value_and_grad() {
+    let grads = grad(f)(&primals()).unwrap();
+    assert_eq!(grads[0], array![6.0, -4.0].into_dyn());
+    assert_eq!(grads[1], array![1.0, 4.0].into_dyn());
+
+    let (value, grads) = value_and_grad(f)(&primals()).unwrap();
+    assert_eq!(value, ndarray::arr0(-1.0).into_dyn());
+    assert_eq!(grads[1], array![1.0, 4.0].into_dyn());
+
+    let err = grad(|v: &[Autograd]| v[0].exp())(&primals());
+    assert_eq!(err, Err(Error::NonScalarRoot { shape: vec![2] }));
+}
+
+#[test]
+fn test_caller_tensors_untouched() {
+    let w = Autograd::new(array![2.0, 3.0]);
+    let loss = |v: &[Autograd]| v[0].mul(&w).sum().pow(2.0);
+
+    let grads = grad(loss)(&[array![1.0, 1.0].into_dyn()]).unwrap();
+    assert_eq!(grads[0], array![20.0, 30.0].into_dyn());
+    hvp(
+        loss,
+        &[array![1.0, 1.0].into_dyn()],
+        &[array![1.0, 0.0].into_dyn()],
+    )
+    .unwrap();
+
+    assert_eq!(w.grad(), array![0.0, 0.0].into_dyn());
+    assert!(w.grad_node().is_none());
+}
+
+#[test]
+fn test_jacobian() {
+    let g = |v: &[Autograd]| v[0].mul(&v[1]).exp();
+    let reverse = jacobian(g, &primals()).unwrap();
+    let forward = jacfwd(g, &primals()).unwrap();
+    assert_eq!(reverse[0].shape(), &[2, 2]);
+    assert_eq!(reverse, forward);
+    assert_eq!(reverse[0][[0, 1]], 0.0);
+    assert_eq!(reverse[0][[1, 1]], -(-2f64).exp());
+}
+
+#[test]
+fn test_hessian_and_hvp() {
+    let h = hessian(f, &primals()).unwrap();
+    // H_xx = diag(2y), H_xy = H_yx = diag(2x), H_yy = 0
+    assert_eq!(h[0][0], array![[6.0, 0.0], [0.0, -2.0]].into_dyn());
+    assert_eq!(h[0][1], array![[2.0, 0.0], [0.0, 4.0]].into_dyn());
+    assert_eq!(h[1][0], array![[2.0, 0.0], [0.0, 4.0]].into_dyn());
+    assert_eq!(h[1][1], array![[0.0, 0.0], [0.0, 0.0]].into_dyn());
+
+    let v = vec![array![1.0, -1.0].into_dyn(), array![0.5, 2.0].into_dyn()];
+    let hv = hvp(f, &primals(), &v).unwrap();
+    assert_eq!(hv[0], array![6.0 + 1.0, 2.0 + 8.0].into_dyn());
+    assert_eq!(hv[1], array![2.0, -4.0].into_dyn());
+}
+
+#[test]
+fn test_transforms_inside_no_grad() {
+    let _guard = no_grad();
+    let h = hessian(
+        |v: &[Autograd]| v[0].pow(3.0).sum(),
+        &[array![2.0].into_dyn()],
+    )
+    .unwrap();
+    assert_eq!(h[0][0], array![[12.0]].into_dyn());
+
+    // A function that ignores its input has zero derivatives
+    let constant = |_: &[Autograd]| Autograd::scalar(1.0);
+    assert_eq!(
+        grad(constant)(&[array![2.0].into_dyn()]).unwrap()[0],
+        array![0.0].into_dyn()
+    );
+    assert_eq!(
+        hessian(constant, &[array![2.0].into_dyn()]).unwrap()[0][0],
+        array![[0.0]].into_dyn()
+    );
+}
+
+#[test]
+fn test_transforms_capture_released_node() {
+    let w = Autograd::new(array![0.0, 1.0]);
+    let h = w.exp();
+    h.sum().backward().unwrap();
+
+    // `h` lost its edges, but it cannot lead to the fresh inputs anyway
+    let g = |v: &[Autograd]| v[0].pow(2.0).mul(&h).sum();
+    let x = [array![1.0, 2.0].into_dyn()];
+    let e = 1f64.exp();
+    assert_eq!(grad(g)(&x).unwrap()[0], array![2.0, 4.0 * e].into_dyn());
+    assert_eq!(
+        jacobian(|v: &[Autograd]| v[0].mul(&h), &x).unwrap()[0],
+        array![[1.0, 0.0], [0.0, e]].into_dyn()
+    );
+    assert_eq!(
+        hessian(g, &x).unwrap()[0][0],
+        array![[2.0, 0.0], [0.0, 2.0 * e]].into_dyn()
+    );
+    assert_eq!(
+        hvp(g, &x, &[array![1.0, 1.0].into_dyn()]).unwrap()[0],
+        array![2.0, 2.0 * e].into_dyn()
+    );
+    assert_eq!(w.grad(), array![1.0, e].into_dyn());
+}
@@ -0,0 +1,163 @@
+use ndarray::{Array2, array};
+use rust_autograd::autograd::Autograd;
+
+#[test]
+fn test_second_derivative_pow() {
+    let x = Autograd::new(array![[2.0, 3.0]]);
+    let y = x.pow(3.0).sum();
+    y.backward_create_graph().unwrap();
+
+    // dy/dx = 3x^2
+    let dx = x.grad_node().unwrap();
+    assert_eq!(dx.value(), array![[12.0, 27.0]].into_dyn());
+    assert_eq!(x.grad(), array![[12.0, 27.0]].into_dyn());
+
+    // d2y/dx2 = 6x
+    x.zero_grad();
+    let dx_sum = dx.sum();
+    dx_sum.backward().unwrap();
+    assert_eq!(x.grad(), array![[12.0, 18.0]].into_dyn());
+}
+
+#[test]
+fn test_second_derivative_tanh() {
+    let x = Autograd::new(array![[0.5]]);
+    let y = x.tanh();
+    y.backward_create_graph().unwrap();
+
+    let dx = x.grad_node().unwrap();
+    x.zero_grad();
+    dx.backward().unwrap();
+
+    // d2/dx2 tanh(x) = -2 tanh(x) (1 - tanh(x)^2)
+    let t = 0.5f64.tanh();
+    assert!((x.grad()[[0, 0]] - (-2.0 * t * (1.0 - t * t))).abs() < 1e-10);
+}
+
+#[test]
+fn test_hessian_vector_product() {
+    // f(w) = sum((x @ w)^2) has Hessian H = 2 x^T x
+    let x = Autograd::new(array![[1.0, 2.0], [3.0, 4.0]]);
+    let w = Autograd::new(array![[0.5], [-1.0]]);
+    let v = Autograd::new(array![[1.0], [0.0]]);
+
+    let f = x.matmul(&w).pow(2.0).sum();
+    f.backward_create_graph().unwrap();
+
+    let g = w.grad_node().unwrap();
+    w.zero_grad();
+    let gv = g.mul(&v).sum();
+    gv.backward().unwrap();
+
+    // H v = 2 x^T x v = 2 * [[10, 14], [14, 20]] @ [1, 0]
+    assert_eq!(w.grad(), array![[20.0], [28.0]].into_dyn());
+}
+
+#[test]
+fn test_gradient_penalty() {
+    // penalty = sum((d/dx sum(exp(x) * s))^2) = sum((exp(x) * s)^2)
+    let x: Autograd = Autograd::new(array![[0.0, 1.0]]);
+    let s = Autograd::new(array![[2.0, 1.0]]);
+    let y = x.exp().mul(&s).sum();
+    y.backward_create_graph().unwrap();
+
+    let g = x.grad_node().unwrap();
+    x.zero_grad();
+    s.zero_grad();
+    let penalty = g.mul(&g).sum();
+    penalty.backward().unwrap();
+
+    // d/dx (exp(x) s)^2 = 2 s^2 exp(2x)
+    let e = std::f64::consts::E;
+    assert!((x.grad()[[0, 0]] - 8.0).abs() < 1e-10);
+    assert!((x.grad()[[0, 1]] - 2.0 * e * e).abs() < 1e-10);
+    // d/ds (exp(x) s)^2 = 2 s exp(2x)
+    assert!((s.grad()[[0, 0]] - 4.0).abs() < 1e-10);
+}
+
+#[test]
+fn test_create_graph_shape_ops() {
+    // y = sum(concat(x, x)^T ^ 2) -> d2y/dx2 = 4 everywhere
+    let x = Autograd::new(array![[1.0, 2.0]]);
+    let y = Autograd::concat(&[x.clone(), x.clone()], 0)
+        .transpose()
+        .pow(2.0)
+        .sum();
+    y.backward_create_graph().unwrap();
+
+    let g = x.grad_node().unwrap();
+    assert_eq!(g.value(), array![[4.0, 8.0]].into_dyn());
+
+    x.zero_grad();
+    g.backward_with(&Array2::ones((1, 2))).unwrap();
+    assert_eq!(x.grad(), array![[4.0, 4.0]].into_dyn());
+}
+
+#[test]
+fn test_create_graph_activations() {
+    let activations: [fn(&Autograd) -> Autograd; 6] = [
+        |x| x.sigmoid(),
+        |x| x.gelu(),
+        |x| x.silu(),
+        |x| x.leaky_relu(0.1),
+        |x| x.elu(0.7),
+        |x| x.softplus(),
+    ];
+    let first = |f: fn(&Autograd) -> Autograd, v: f64| {
+        let x = Autograd::new(array![[v]]);
+        f(&x).sum().backward().unwrap();
+        x.grad()[[0, 0]]
+    };
+
+    for f in activations {
+        for v in [-1.3, 0.4, 2.2] {
+            let x = Autograd::new(array![[v]]);
+            f(&x).sum().backward_create_graph().unwrap();
+            let dx = x.grad_node().unwrap();
+            x.zero_grad();
+            // The gradient of a piecewise-linear op does not depend on x at all
+            if dx.requires_grad() {
+                dx.sum().backward().unwrap();
+            }
+
+            let eps = 1e-5;
+            let numeric = (first(f, v + eps) - first(f, v - eps)) / (2.0 * eps);
+            assert!((x.grad()[[0, 0]] - numeric).abs() < 1e-5);
+        }
+    }
+}
+
+#[test]
+fn test_create_graph_logsumexp() {
+    // d/dx logsumexp(x) = softmax(x), and the sum of softmax over the axis is 1,
+    // so its gradient vanishes
+    let x: Autograd = Autograd::new(array![[0.3, -1.2, 2.5]]);
+    x.logsumexp().backward_create_graph().unwrap();
+    let dx = x.grad_node().unwrap();
+    assert!((dx.value().sum() - 1.0).abs() < 1e-12);
+
+    x.zero_grad();
+    dx.mul(&x.softmax(1)).sum().backward().unwrap();
+    // sum(s * s) has gradient 2 * s * (s - sum(s * s))
+    let s = x.softmax(1).value();
+    let expected = &s * &(&s - (&s * &s).sum()) * 2.0;
+    assert!((x.grad() - expected).iter().all(|d| d.abs() < 1e-12));
+}
+
+#[test]
+fn test_create_graph_trig_sqrt() {
+    let x: Autograd = Autograd::new(array![[0.7]]);
+    x.sin()
+        .add(&x.cos())
+        .add(&x.sqrt())
+        .sum()
+        .backward_create_graph()
+        .unwrap();
+    let dx = x.grad_node().unwrap();
+    x.zero_grad();
+    dx.sum().backward().unwrap();
+
+    let v: f64 = 0.7;
+    let expected = -v.sin() - v.cos() - 0.25 * v.powf(-1.5);
+    assert!((x.grad()[[0, 0]] - expected).abs() < 1e-12);
+}
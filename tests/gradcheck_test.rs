@@ -0,0 +1,202 @@
+use ndarray::{ArrayD, array};
+use rust_autograd::autograd::{Autograd, Function, no_grad};
+use rust_autograd::error::Error;
+use rust_autograd::helpers::gradcheck::{GradCheck, gradcheck};
+use rust_autograd::nn::{Activation, Layer, Neuron};
+
+#[test]
+fn test_gradcheck_builtin_ops() {
+    let a = Autograd::new(array![[0.5, -1.0], [2.0, 0.3]]);
+    let b = Autograd::new(array![[1.5], [0.7]]);
+
+    let report = gradcheck(
+        |x| {
+            x[0].matmul(&x[1])
+                .tanh()
+                .add(&x[1].exp())
+                .div(&x[1].pow(2.0).add(&x[1]))
+                .sum()
+        },
+        &[a.clone(), b.clone()],
+    )
+    .unwrap();
+    assert!(report.is_ok(), "{}", report);
+
+    // the caller's tensors are not touched
+    assert_eq!(a.grad(), ArrayD::zeros(a.value().raw_dim()));
+    assert_eq!(b.grad(), ArrayD::zeros(b.value().raw_dim()));
+}
+
+#[test]
+fn test_gradcheck_reductions_and_shapes() {
+    let a = Autograd::new(array![[0.1, 0.9, -0.4], [1.2, -0.8, 0.5]]);
+
+    let report = GradCheck::new()
+        .eps(1e-5)
+        .check(
+            |x| {
+                let t = x[0].transpose().reshape(&[2, 3]);
+                let parts = Autograd::concat(&[t.slice_axis(1, 0, 2), x[0].slice_axis(1, 1, 3)], 0);
+                parts.max_axis(1, true).sum().add(&parts.mean())
+            },
+            &[a],
+        )
+        .unwrap();
+    assert!(report.is_ok(), "{}", report);
+}
+
+#[test]
+fn test_gradcheck_layers() {
+    let x: Vec<Autograd> = [0.5, -1.0, 2.0]
+        .iter()
+        .map(|&v| Autograd::new(array![[v]]))
+        .collect();
+
+    let neuron = Neuron::new(3, 42);
+    let report = gradcheck(|x| neuron.call(x, Activation::Tanh), &x).unwrap();
+    assert!(report.is_ok(), "{}", report);
+
+    let layer = Layer::new(3, 2, Activation::Softmax, 7);
+    let report = gradcheck(|x| layer.call(x)[0].clone(), &x).unwrap();
+    assert!(report.is_ok(), "{}", report);
+}
+
+struct WrongSquare;
+
+impl Function for WrongSquare {
+    fn name(&self) -> &str {
+        "WrongSquare"
+    }
+
+    fn forward(&self, inputs: &[&ArrayD<f64>]) -> ArrayD<f64> {
+        inputs[0].mapv(|x| x * x)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[&ArrayD<f64>],
+        _output: &ArrayD<f64>,
+        grad: &ArrayD<f64>,
+    ) -> Vec<ArrayD<f64>> {
+        // should be 2x
+        vec![grad * inputs[0]]
+    }
+}
+
+#[test]
+fn test_gradcheck_reports_mismatches() {
+    let a = Autograd::new(array![[1.0, 0.0, 3.0]]);
+
+    let report = gradcheck(|x| Autograd::apply(WrongSquare, x).sum(), &[a]).unwrap();
+    assert!(!report.is_ok());
+    // x = 0 is the only element where x and 2x agree
+    assert_eq!(report.mismatches.len(), 2);
+    assert_eq!(report.mismatches[0].input, 0);
+    assert_eq!(report.mismatches[0].index, vec![0, 0]);
+    assert!((report.mismatches[0].analytic - 1.0).abs() < 1e-10);
+    assert!((report.mismatches[0].numeric - 2.0).abs() < 1e-4);
+    assert_eq!(report.mismatches[1].index, vec![0, 2]);
+    assert!(report.to_string().contains("2 mismatches"));
+}
+
+fn activations(x: &Autograd) -> Autograd {
+    x.sigmoid()
+        .add(&x.gelu())
+        .add(&x.silu())
+        .add(&x.leaky_relu(0.1))
+        .add(&x.elu(0.7))
+        .add(&x.softplus())
+        .sum()
+}
+
+#[test]
+fn test_gradcheck_activations() {
+    let a = Autograd::new(array![[-2.0, -0.5, 0.3], [1.1, 2.5, -4.0]]);
+
+    let report = gradcheck(|x| activations(&x[0]), &[a]).unwrap();
+    assert!(report.is_ok(), "{}", report);
+}
+
+#[test]
+fn test_gradcheck_softmax_family() {
+    let a = Autograd::new(array![[0.2, -1.3, 2.0], [0.7, 0.1, -0.4]]);
+    let w = Autograd::new(array![[1.0, 2.0, -1.0], [0.5, -2.0, 3.0]]);
+
+    let report = gradcheck(
+        |x| {
+            x[0].softmax(1)
+                .mul(&x[1])
+                .add(&x[0].log_softmax(0).mul(&x[1]))
+                .sum()
+                .add(&x[0].logsumexp_axis(1, true).sum())
+                .add(&x[0].logsumexp())
+        },
+        &[a, w],
+    )
+    .unwrap();
+    assert!(report.is_ok(), "{}", report);
+}
+
+#[test]
+fn test_gradcheck_elementwise_math() {
+    let a = Autograd::new(array![[0.5, -1.2, 2.0], [1.4, -0.3, 0.9]]);
+    let b = Autograd::new(array![[0.8, -2.0, 1.1]]);
+    let cond = array![[true, false, true], [false, false, true]];
+
+    let report = gradcheck(
+        |x| {
+            let (a, b) = (&x[0], &x[1]);
+            a.mul(a)
+                .add(&b.mul(b))
+                .sqrt()
+                .add(&a.abs())
+                .add(&a.sin().mul(&b.cos()))
+                .add(&a.clamp(-1.0, 1.0))
+                .add(&a.minimum(b))
+                .add(&a.maximum(b).mul(b))
+                .add(&Autograd::where_(&cond, &a.exp(), b))
+                .sum()
+        },
+        &[a, b],
+    )
+    .unwrap();
+    assert!(report.is_ok(), "{}", report);
+}
+
+#[test]
+fn test_gradcheck_f32() {
+    let a: Autograd<f32> = Autograd::new(array![[0.5, -1.0, 2.0], [0.3, 1.7, -0.2]]);
+
+    let report = gradcheck(|x| x[0].tanh().mul(&x[0]).sum(), std::slice::from_ref(&a)).unwrap();
+    assert!(report.is_ok(), "{}", report);
+
+    let report = gradcheck(
+        |x| x[0].softmax(1).mul(&x[0]).sigmoid().mean(),
+        std::slice::from_ref(&a),
+    )
+    .unwrap();
+    assert!(report.is_ok(), "{}", report);
+}
+
+#[test]
+fn test_gradcheck_inside_no_grad() {
+    let a = Autograd::new(array![[0.5, -1.0]]);
+
+    let _guard = no_grad();
+    let report = gradcheck(|x| x[0].exp().sum(), &[a]).unwrap();
+    assert!(report.is_ok(), "{}", report);
+}
+
+#[test]
+fn test_gradcheck_constant_and_non_scalar() {
+    let a = Autograd::new(array![[0.5, -1.0]]);
+
+    // An output that ignores the inputs has zero gradients, which is correct
+    let report = gradcheck(|_| Autograd::scalar(2.0), std::slice::from_ref(&a)).unwrap();
+    assert!(report.is_ok(), "{}", report);
+
+    assert_eq!(
+        gradcheck(|x| x[0].exp(), &[a]).unwrap_err(),
+        Error::NonScalarRoot { shape: vec![1, 2] }
+    );
+}
@@ -0,0 +1,46 @@
+use ndarray::array;
+use rust_autograd::autograd::Autograd;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn test_hook_rewrites_gradient() {
+    let x: Autograd = Autograd::new(array![[1.0, -2.0]]);
+    let h = x.mul(&x);
+    // Clip the gradient flowing through h before it reaches x
+    h.register_hook(|grad| Some(grad.mapv(|g| g.clamp(-1.0, 1.0))));
+    let y = h.mul(&Autograd::constant(array![[5.0, -5.0]])).sum();
+
+    y.backward().unwrap();
+    assert_eq!(h.grad(), array![[1.0, -1.0]].into_dyn());
+    assert_eq!(x.grad(), array![[2.0, 4.0]].into_dyn());
+}
+
+#[test]
+fn test_hook_observes_and_removes() {
+    let x = Autograd::new(array![[3.0]]);
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let log = seen.clone();
+    let handle = x.register_hook(move |grad| {
+        log.borrow_mut().push(grad[[0, 0]]);
+        None
+    });
+
+    x.mul(&x).sum().backward().unwrap();
+    assert_eq!(*seen.borrow(), vec![6.0]);
+    assert_eq!(x.grad(), array![[6.0]].into_dyn());
+
+    handle.remove();
+    x.exp().sum().backward().unwrap();
+    assert_eq!(seen.borrow().len(), 1);
+}
+
+#[test]
+fn test_hooks_run_in_order() {
+    let x = Autograd::new(array![[1.0]]);
+    x.register_hook(|grad| Some(grad + 1.0));
+    x.register_hook(|grad| Some(grad * 10.0));
+
+    x.mul(&x).sum().backward().unwrap();
+    assert_eq!(x.grad(), array![[30.0]].into_dyn());
+}
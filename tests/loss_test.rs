@@ -4,7 +4,7 @@ use rust_autograd::loss::{Loss, MSE, SoftmaxCrossEntropyLoss};
 
 #[test]
 fn test_mse_loss() {
-    let pred = vec![Autograd::new(array![[0.1]]), Autograd::new(array![[0.9]])];
+    let pred: Vec<Autograd> = vec![Autograd::new(array![[0.1]]), Autograd::new(array![[0.9]])];
     let target_index = 1;
     let loss_fn = MSE::new();
     let loss = loss_fn.forward(&pred, target_index);
@@ -14,8 +14,7 @@ fn test_mse_loss() {
     //     = (0.01 + 0.01) / 2 = 0.01
     assert!((loss.value()[[0, 0]] - 0.01).abs() < 1e-7);
 
-    loss.set_grad(array![[1.0]]);
-    loss.backward();
+    loss.backward().unwrap();
 
     // dL/dp0 = (2/N) * (p0 - t0) = (2/2) * (0.1 - 0.0) = 0.1
     // dL/dp1 = (2/N) * (p1 - t1) = (2/2) * (0.9 - 1.0) = -0.1
@@ -23,19 +22,54 @@ fn test_mse_loss() {
     assert!((pred[1].grad()[[0, 0]] - (-0.1)).abs() < 1e-7);
 }
 
+#[test]
+fn test_mse_loss_batch() {
+    let pred: Vec<Autograd> = vec![
+        Autograd::new(array![[0.5], [0.0]]),
+        Autograd::new(array![[0.5], [1.0]]),
+        Autograd::new(array![[0.0], [1.0]]),
+    ];
+    let loss = MSE::new().forward(&pred, 1);
+    assert_eq!(loss.value(), array![[0.5 / 3.0], [1.0 / 3.0]].into_dyn());
+
+    // One node per operand and op, however many classes there are
+    let wide: Vec<Autograd> = (0..10).map(|_| Autograd::new(array![[0.0]])).collect();
+    let wide_loss = MSE::new().forward(&wide, 1);
+    assert_eq!(
+        wide_loss.get_topo().len() - wide.len(),
+        loss.get_topo().len() - pred.len()
+    );
+}
+
 #[test]
 fn test_softmax_cross_entropy_loss() {
-    let pred = vec![Autograd::new(array![[0.1]]), Autograd::new(array![[0.9]])];
+    let pred: Vec<Autograd> = vec![Autograd::new(array![[0.1]]), Autograd::new(array![[0.9]])];
     let target_index = 1;
     let loss_fn = SoftmaxCrossEntropyLoss::new();
     let loss = loss_fn.forward(&pred, target_index);
 
-    // loss = -ln(pred[target_index]) = -ln(0.9)
-    assert!((loss.value()[[0, 0]] - (-0.9f64.ln())).abs() < 1e-10);
+    // loss = -log_softmax(pred)[target] = ln(e^0.1 + e^0.9) - 0.9
+    let lse = (0.1f64.exp() + 0.9f64.exp()).ln();
+    assert!((loss.value()[[0, 0]] - (lse - 0.9)).abs() < 1e-10);
+
+    loss.backward().unwrap();
+
+    // dL/dlogit = softmax(pred) - onehot(target)
+    let p1 = (0.9 - lse).exp();
+    assert!((pred[0].grad()[[0, 0]] - (1.0 - p1)).abs() < 1e-10);
+    assert!((pred[1].grad()[[0, 0]] - (p1 - 1.0)).abs() < 1e-10);
+}
+
+#[test]
+fn test_softmax_cross_entropy_large_logits() {
+    let pred: Vec<Autograd> = vec![Autograd::new(array![[0.0]]), Autograd::new(array![[800.0]])];
+    let loss = SoftmaxCrossEntropyLoss::new().forward(&pred, 0);
 
-    loss.set_grad(array![[1.0]]);
-    loss.backward();
+    // The target class has log-probability -800, which log(softmax) cannot
+    // represent
+    assert_eq!(loss.value(), array![[800.0]].into_dyn());
 
-    // dL/dp[target] = -1/p[target] = -1/0.9
-    assert!((pred[target_index].grad()[[0, 0]] - (-1.0 / 0.9)).abs() < 1e-7);
+    loss.backward().unwrap();
+    assert_eq!(pred[0].grad(), array![[-1.0]].into_dyn());
+    assert_eq!(pred[1].grad(), array![[1.0]].into_dyn());
 }
@@ -27,7 +27,7 @@ fn test_layer() {
 
 #[test]
 fn test_layer_softmax_deterministic() {
-    let l = Layer::new(2, 2, Activation::Softmax, 42);
+    let l: Layer = Layer::new(2, 2, Activation::Softmax, 42);
 
     for p in l.parameters() {
         p.set_value(array![[0.0]]);
@@ -64,3 +64,37 @@ fn test_mlp() {
     // Total: 17
     assert_eq!(params.len(), 17);
 }
+
+#[test]
+fn test_mlp_logits() {
+    let mlp: MLP = MLP::new(2, &[4, 3], 42);
+    let x = vec![Autograd::new(array![[1.0]]), Autograd::new(array![[-2.0]])];
+    let probs = mlp.call(&x);
+    let logits = Autograd::concat(&mlp.logits(&x), 1).softmax(1).value();
+    for (i, p) in probs.iter().enumerate() {
+        assert!((p.value()[[0, 0]] - logits[[0, i]]).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_activations() {
+    let x: Vec<Autograd> = vec![Autograd::new(array![[0.5]]), Autograd::new(array![[-1.5]])];
+    for activation in [
+        Activation::Sigmoid,
+        Activation::Gelu,
+        Activation::SiLU,
+        Activation::LeakyReLU(0.01),
+        Activation::Elu(1.0),
+        Activation::Softplus,
+    ] {
+        let neuron = Neuron::new(2, 42);
+        let out = neuron.call(&x, activation);
+        out.backward().unwrap();
+        assert!(out.value()[[0, 0]].is_finite());
+        assert!(neuron.parameters().iter().all(|p| p.grad()[[0, 0]] != 0.0));
+    }
+
+    let neuron: Neuron = Neuron::new(2, 42);
+    let out = neuron.call(&x, Activation::Sigmoid).value()[[0, 0]];
+    assert!(out > 0.0 && out < 1.0);
+}
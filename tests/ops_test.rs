@@ -0,0 +1,44 @@
+use ndarray::array;
+use rust_autograd::autograd::Autograd;
+
+#[test]
+fn test_tensor_operators() {
+    let a = Autograd::new(array![[1.0, 2.0]]);
+    let b = Autograd::new(array![[3.0, 4.0]]);
+
+    // y = (a + b) * a - b / a
+    let y = (&a + &b) * &a - &b / &a;
+    assert_eq!(y.value(), array![[1.0, 10.0]].into_dyn());
+
+    y.backward_with(&array![[1.0, 1.0]]).unwrap();
+    // dy/da = 2a + b + b/a^2, dy/db = a - 1/a
+    assert_eq!(a.grad(), array![[8.0, 9.0]].into_dyn());
+    assert_eq!(b.grad(), array![[0.0, 1.5]].into_dyn());
+}
+
+#[test]
+fn test_scalar_operators() {
+    let p: Autograd = Autograd::new(array![[0.25, 0.5]]);
+
+    let y = (1.0 - &p) * 2.0 + &p / 4.0 - 1.0;
+    assert_eq!(y.value(), array![[0.5625, 0.125]].into_dyn());
+
+    y.backward_with(&array![[1.0, 1.0]]).unwrap();
+    assert_eq!(p.grad(), array![[-1.75, -1.75]].into_dyn());
+
+    let q = 2.0 / &p + 1.0 * &p;
+    assert_eq!(q.value(), array![[8.25, 4.5]].into_dyn());
+}
+
+#[test]
+fn test_neg_operator() {
+    let a = Autograd::new(array![[1.0f32, -2.0]]);
+    let b = -&a * 3.0f32;
+    assert_eq!(b.value(), array![[-3.0f32, 6.0]].into_dyn());
+
+    b.backward_with(&array![[1.0f32, 1.0]]).unwrap();
+    assert_eq!(a.grad(), array![[-3.0f32, -3.0]].into_dyn());
+
+    let c = 1.0f32 - -a;
+    assert_eq!(c.value(), array![[2.0f32, -1.0]].into_dyn());
+}
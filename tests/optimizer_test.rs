@@ -4,7 +4,7 @@ use rust_autograd::optimizer::{AdamW, Optimizer, SGD};
 
 #[test]
 fn test_sgd_optimizer() {
-    let p = Autograd::new(array![[10.0]]);
+    let p: Autograd = Autograd::new(array![[10.0]]);
     let params = vec![p.clone()];
     let mut optim = SGD::new(0.1);
 
@@ -17,7 +17,7 @@ fn test_sgd_optimizer() {
 
 #[test]
 fn test_adamw_optimizer() {
-    let p = Autograd::new(array![[1.0]]);
+    let p: Autograd = Autograd::new(array![[1.0]]);
     let params = vec![p.clone()];
     // lr=0.1, beta1=0.9, beta2=0.999, eps=1e-8, wd=0.0
     let mut optim = AdamW::new(0.1);
@@ -35,3 +35,34 @@ fn test_adamw_optimizer() {
     // w = w - lr * update = 1.0 - 0.1 * 1.0 = 0.9
     assert!((p.value()[[0, 0]] - 0.9).abs() < 1e-7);
 }
+
+#[test]
+fn test_optimizers_f32() {
+    let p = Autograd::new(array![[10.0f32]]);
+    let params = vec![p.clone()];
+    let mut optim = SGD::new(0.1);
+
+    p.set_grad(array![[2.0f32]]);
+    optim.step(&params);
+    assert!((p.value()[[0, 0]] - 9.8).abs() < 1e-5);
+
+    let p = Autograd::new(array![[1.0f32]]);
+    let params = vec![p.clone()];
+    let mut optim = AdamW::new(0.1);
+
+    p.set_grad(array![[0.1f32]]);
+    optim.step(&params);
+    assert!((p.value()[[0, 0]] - 0.9).abs() < 1e-5);
+}
+
+#[test]
+fn test_optimizers_skip_frozen() {
+    let p: Autograd = Autograd::new(array![[10.0]]);
+    p.set_grad(array![[2.0]]);
+    p.set_requires_grad(false);
+    let params = vec![p.clone()];
+
+    SGD::new(0.1).step(&params);
+    AdamW::new(0.1).step(&params);
+    assert_eq!(p.value(), array![[10.0]].into_dyn());
+}
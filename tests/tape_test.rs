@@ -0,0 +1,161 @@
+use ndarray::array;
+use rust_autograd::autograd::{Autograd, Tape};
+use rust_autograd::error::Error;
+
+#[test]
+fn test_tape_matches_graph() {
+    let (x, w, b) = (
+        array![[1.0, -2.0], [0.5, 3.0]],
+        array![[0.1, 0.2, -0.3], [0.4, -0.5, 0.6]],
+        array![[0.1, 0.0, -0.1]],
+    );
+
+    // The same two-layer expression on both engines
+    let xa = Autograd::constant(x.clone());
+    let wa = Autograd::new(w.clone());
+    let ba = Autograd::new(b.clone());
+    let loss = xa.matmul(&wa).add(&ba).tanh().log_softmax(1).mean().neg();
+    loss.backward().unwrap();
+
+    let mut tape = Tape::new();
+    let xt = tape.constant(x);
+    let wt = tape.leaf(w);
+    let bt = tape.leaf(b);
+    let h = tape.matmul(xt, wt);
+    let h = tape.add(h, bt);
+    let h = tape.tanh(h);
+    let h = tape.log_softmax(h, 1);
+    let h = tape.mean(h);
+    let loss_t = tape.neg(h);
+    let grads = tape.backward(loss_t).unwrap();
+
+    assert_eq!(tape.value(loss_t), &loss.value());
+    assert_eq!(grads[wt], wa.grad());
+    assert_eq!(grads[bt], ba.grad());
+    assert!(grads.get(xt).is_none());
+}
+
+#[test]
+fn test_tape_accumulates_and_reruns() {
+    let mut tape = Tape::new();
+    let x = tape.leaf(array![2.0, 3.0]);
+    let unused = tape.leaf(array![1.0]);
+    // y = x * x + x^3
+    let sq = tape.mul(x, x);
+    let cube = tape.pow(x, 3.0);
+    let y = tape.add(sq, cube);
+    let y = tape.sum(y);
+
+    let grads = tape.backward(y).unwrap();
+    assert_eq!(grads[x], array![16.0, 33.0].into_dyn());
+    assert!(grads.get(unused).is_none());
+
+    // The tape is not consumed, so it can be differentiated again
+    let grads = tape.backward_with(sq, &array![1.0, 0.0]).unwrap();
+    assert_eq!(grads[x], array![4.0, 0.0].into_dyn());
+
+    tape.clear();
+    assert!(tape.is_empty());
+    let x = tape.leaf(array![1.0]);
+    let y = tape.exp(x);
+    let y = tape.sum(y);
+    assert_eq!(tape.len(), 3);
+    assert_eq!(tape.backward(y).unwrap()[x], array![1f64.exp()].into_dyn());
+}
+
+#[test]
+fn test_tape_errors() {
+    let mut tape = Tape::new();
+    let x = tape.leaf(array![1.0, 2.0]);
+    let c = tape.constant(array![3.0]);
+    let y = tape.exp(x);
+    let z = tape.sum(c);
+
+    assert_eq!(
+        tape.backward(y).err(),
+        Some(Error::NonScalarRoot { shape: vec![2] })
+    );
+    assert_eq!(
+        tape.backward_with(y, &array![1.0]).err(),
+        Some(Error::GradShape {
+            expected: vec![2],
+            got: vec![1]
+        })
+    );
+    assert!(matches!(tape.backward(z), Err(Error::NoGrad { .. })));
+}
+
+#[test]
+fn test_tape_set_value_and_forward() {
+    let mut tape = Tape::new();
+    let w = tape.leaf(array![1.0, 2.0]);
+    let y = tape.mul(w, w);
+    let loss = tape.sum(y);
+
+    tape.set_value(w, array![3.0, -1.0]);
+    // Stale until the tape is re-run
+    assert_eq!(tape.value(loss), &ndarray::arr0(5.0).into_dyn());
+    tape.forward();
+    assert_eq!(tape.value(loss), &ndarray::arr0(10.0).into_dyn());
+    assert_eq!(
+        tape.backward(loss).unwrap()[w],
+        array![6.0, -2.0].into_dyn()
+    );
+}
+
+#[test]
+#[should_panic(expected = "only leaves can be set")]
+fn test_tape_set_value_on_computed_node() {
+    let mut tape = Tape::new();
+    let x = tape.leaf(array![1.0]);
+    let y = tape.exp(x);
+    tape.set_value(y, array![2.0]);
+}
+
+#[test]
+fn test_tape_ops_match_graph() {
+    let x = array![[0.5, -1.5, 2.0], [1.0, 0.25, -0.75]];
+    let cond = array![[true, false, true]];
+
+    let xa = Autograd::new(x.clone());
+    let a = xa.slice_axis(1, 0, 2).sin().leaky_relu(0.1);
+    let b = xa.slice_axis(1, 1, 3).cos().elu(1.0).softplus();
+    let m = Autograd::stack(&[a.minimum(&b), a.maximum(&b)], 0);
+    let w = Autograd::where_(&cond, &xa.clamp(-1.0, 1.0), &xa.transpose().transpose());
+    let loss = m.max_axis(0, false).mean_axis(1, true).sum()
+        + m.min() * w.logsumexp_axis(1, false).sum()
+        + w.stop_gradient().logsumexp();
+    loss.backward().unwrap();
+
+    let mut tape = Tape::new();
+    let xt = tape.leaf(x);
+    let s = tape.slice_axis(xt, 1, 0, 2);
+    let s = tape.sin(s);
+    let a = tape.leaky_relu(s, 0.1);
+    let s = tape.slice_axis(xt, 1, 1, 3);
+    let s = tape.cos(s);
+    let s = tape.elu(s, 1.0);
+    let b = tape.softplus(s);
+    let lo = tape.minimum(a, b);
+    let hi = tape.maximum(a, b);
+    let m = tape.stack(&[lo, hi], 0);
+    let c = tape.clamp(xt, -1.0, 1.0);
+    let t = tape.transpose(xt);
+    let t = tape.transpose(t);
+    let w = tape.where_(&cond, c, t);
+    let l1 = tape.max_axis(m, 0, false);
+    let l1 = tape.mean_axis(l1, 1, true);
+    let l1 = tape.sum(l1);
+    let l2 = tape.min(m);
+    let l3 = tape.logsumexp_axis(w, 1, false);
+    let l3 = tape.sum(l3);
+    let l2 = tape.mul(l2, l3);
+    let l4 = tape.stop_gradient(w);
+    let l4 = tape.logsumexp(l4);
+    let total = tape.add(l1, l2);
+    let total = tape.add(total, l4);
+    let grads = tape.backward(total).unwrap();
+
+    assert_eq!(tape.value(total), &loss.value());
+    assert_eq!(grads[xt], xa.grad());
+}
@@ -0,0 +1,131 @@
+use ndarray::{ArrayD, array};
+use rust_autograd::autograd::{Autograd, trace};
+use rust_autograd::error::Error;
+
+#[test]
+fn test_trace_matches_eager() {
+    let w = Autograd::new(array![[0.5, -1.0], [2.0, 0.25]]);
+    let model = |x: &[Autograd]| x[0].matmul(&w).tanh().mul(&x[1]).sum();
+
+    let mut graph = trace(
+        model,
+        &[ArrayD::zeros(vec![1, 2]), ArrayD::zeros(vec![1, 2])],
+    );
+    let len = graph.len();
+
+    for (a, b) in [
+        (array![[1.0, 2.0]], array![[3.0, -1.0]]),
+        (array![[-0.5, 0.1]], array![[2.0, 2.0]]),
+    ] {
+        // Reference on a freshly built graph
+        w.zero_grad();
+        let (xa, xb) = (Autograd::new(a.clone()), Autograd::new(b.clone()));
+        let y = model(&[xa.clone(), xb.clone()]);
+        y.backward().unwrap();
+        let expected_w = w.grad();
+
+        w.zero_grad();
+        let value = graph
+            .forward(&[a.into_dyn(), b.into_dyn()])
+            .unwrap()
+            .clone();
+        let grads = graph.backward().unwrap();
+
+        assert_eq!(value, y.value());
+        assert_eq!(grads, vec![xa.grad(), xb.grad()]);
+        assert_eq!(w.grad(), expected_w);
+        assert_eq!(graph.len(), len);
+    }
+}
+
+#[test]
+fn test_trace_reads_captured_tensors() {
+    let w = Autograd::new(array![2.0]);
+    let mut graph = trace(
+        |x: &[Autograd]| x[0].mul(&w).pow(2.0).sum(),
+        &[array![1.0].into_dyn()],
+    );
+
+    w.set_value(array![3.0]);
+    let y = graph.forward(&[array![1.0].into_dyn()]).unwrap();
+    assert_eq!(y, &ndarray::arr0(9.0).into_dyn());
+
+    // Gradients accumulate into captured tensors like with `backward`
+    graph.backward().unwrap();
+    graph.backward().unwrap();
+    assert_eq!(w.grad(), array![12.0].into_dyn());
+}
+
+#[test]
+fn test_trace_unused_input_and_errors() {
+    let mut graph = trace(
+        |x: &[Autograd]| x[0].exp().sum(),
+        &[array![0.0].into_dyn(), array![1.0, 2.0].into_dyn()],
+    );
+    let grads = graph.backward().unwrap();
+    assert_eq!(grads[0], array![1.0].into_dyn());
+    assert_eq!(grads[1], array![0.0, 0.0].into_dyn());
+
+    assert_eq!(
+        graph
+            .forward(&[array![0.0, 1.0].into_dyn(), array![1.0, 2.0].into_dyn()])
+            .err(),
+        Some(Error::ShapeMismatch {
+            op: "trace",
+            shapes: vec![vec![1], vec![2]],
+            names: Vec::new(),
+        })
+    );
+
+    let mut graph = trace(|x: &[Autograd]| x[0].exp(), &[array![0.0, 1.0].into_dyn()]);
+    assert_eq!(
+        graph.backward().err(),
+        Some(Error::NonScalarRoot { shape: vec![2] })
+    );
+}
+
+#[test]
+fn test_trace_captures_released_node() {
+    let w = Autograd::new(array![0.0, 1.0]);
+    let h = w.exp();
+    h.sum().backward().unwrap();
+    assert_eq!(h.op(), "Released");
+
+    let mut graph = trace(
+        |x: &[Autograd]| x[0].mul(&h).sum(),
+        &[array![0.0, 0.0].into_dyn()],
+    );
+    let y = graph.forward(&[array![1.0, 2.0].into_dyn()]).unwrap();
+    assert_eq!(y, &ndarray::arr0(1.0 + 2.0 * 1f64.exp()).into_dyn());
+    let grads = graph.backward().unwrap();
+    assert_eq!(grads[0], h.value());
+}
+
+#[test]
+fn test_trace_rereads_frozen_parameters() {
+    let w = Autograd::new(array![1.0]);
+    w.set_requires_grad(false);
+    let mut graph = trace(
+        |x: &[Autograd]| x[0].mul(&w.exp()).sum(),
+        &[array![1.0].into_dyn()],
+    );
+
+    w.set_value(array![0.0]);
+    let y = graph.forward(&[array![1.0].into_dyn()]).unwrap();
+    assert_eq!(y, &ndarray::arr0(1.0).into_dyn());
+    assert_eq!(graph.backward().unwrap(), vec![array![1.0].into_dyn()]);
+    assert_eq!(w.grad(), array![0.0].into_dyn());
+}
+
+#[test]
+fn test_trace_recomputes_detached_values() {
+    let mut graph = trace(
+        |x: &[Autograd]| x[0].mul(&x[0].detach()).sum(),
+        &[array![1.0].into_dyn()],
+    );
+
+    let y = graph.forward(&[array![3.0].into_dyn()]).unwrap();
+    assert_eq!(y, &ndarray::arr0(9.0).into_dyn());
+    // No gradient flows through the detached factor
+    assert_eq!(graph.backward().unwrap(), vec![array![3.0].into_dyn()]);
+}